@@ -190,11 +190,17 @@ module.exports = async function (provider) {
   // catch (error: any) {
   //   console.log(error);
   // }
-  let initClaimTx = program.methods.iniClaimPublic(
+  const [rewardMintPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("reward_mint"), mint.toBuffer()],
+      program.programId);
+  let initClaimTx = program.methods.registerRewardMint(
+      true,
+      false
   ).accounts({
     state:statePda,
     mint:mint,
     tokenVault:token_vault_ata,
+    rewardMint:rewardMintPda,
     config: configPDA,
     payer:provider.wallet.publicKey,
     systemProgram: anchor.web3.SystemProgram.programId,
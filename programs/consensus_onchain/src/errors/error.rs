@@ -34,4 +34,166 @@ pub enum ErrorCode {
 
     #[msg("Invalid nonce")]
     NonceError,
+
+    #[msg("No ownership transfer pending")]
+    NoPendingOwner,
+
+    #[msg("Only pending owner")]
+    OnlyPendingOwner,
+
+    #[msg("Invalid new owner")]
+    InvalidNewOwner,
+
+    #[msg("Only program upgrade authority")]
+    OnlyUpgradeAuthority,
+
+    #[msg("Reward vault already configured")]
+    VaultAlreadyConfigured,
+
+    #[msg("Signer not authorized")]
+    SignerNotAuthorized,
+
+    #[msg("Signer registry is full")]
+    SignerRegistryFull,
+
+    #[msg("Signer not found")]
+    SignerNotFound,
+
+    #[msg("Invalid signer scope")]
+    InvalidSignerScope,
+
+    #[msg("Invalid slot range")]
+    InvalidSlotRange,
+
+    #[msg("Message must be a valid binary payload.")]
+    MessageShouldBorsh,
+
+    #[msg("Unsupported message version.")]
+    UnsupportedPayloadVersion,
+
+    #[msg("Message was signed for another program.")]
+    DomainProgramMismatch,
+
+    #[msg("Message was signed for another instruction.")]
+    DomainKindMismatch,
+
+    #[msg("Message was signed for another deployment.")]
+    DomainDeploymentMismatch,
+
+    #[msg("Message has expired.")]
+    MessageExpired,
+
+    #[msg("Message validity exceeds the configured maximum.")]
+    ValidityTooLong,
+
+    #[msg("Invalid maximum validity.")]
+    InvalidMaxValidity,
+
+    #[msg("Timestamp does not match the signed message.")]
+    TimestampMismatch,
+
+    #[msg("Task does not match the signed message.")]
+    TaskMismatch,
+
+    #[msg("Quiz id does not match the signed message.")]
+    QuizMismatch,
+
+    #[msg("Malformed signature instruction.")]
+    MalformedSignatureInstruction,
+
+    #[msg("Invalid co-signing policy.")]
+    InvalidCosignPolicy,
+
+    #[msg("Not enough distinct signatures for this amount.")]
+    InsufficientSignatures,
+
+    #[msg("Claim amount exceeds the per-claim limit.")]
+    ClaimAmountTooLarge,
+
+    #[msg("Claim exceeds the user's daily limit.")]
+    UserClaimCapExceeded,
+
+    #[msg("Claim exceeds the epoch limit.")]
+    EpochClaimCapExceeded,
+
+    #[msg("This instruction is paused.")]
+    Paused,
+
+    #[msg("Invalid pause flags.")]
+    InvalidPauseFlags,
+
+    #[msg("Only the owner or the guardian can pause.")]
+    OnlyOwnerOrGuardian,
+
+    #[msg("The guardian can only pause.")]
+    GuardianCannotUnpause,
+
+    #[msg("Withdrawal exceeds the balance above the rent-exempt minimum.")]
+    InsufficientWithdrawableBalance,
+
+    #[msg("Transfer fee calculation failed.")]
+    TransferFeeError,
+
+    #[msg("Reward mint is disabled.")]
+    RewardMintDisabled,
+
+    #[msg("Mint does not match the signed message.")]
+    MintMismatch,
+
+    #[msg("Account layout is not recognised.")]
+    UnknownAccountLayout,
+
+    #[msg("Account is already in the current layout.")]
+    AccountAlreadyMigrated,
+
+    #[msg("Invalid retention period.")]
+    InvalidRetention,
+
+    #[msg("Consensus data is still inside the retention period.")]
+    RetentionNotElapsed,
+
+    #[msg("Invalid accounts for batch close.")]
+    InvalidCloseBatch,
+
+    #[msg("Invalid upload window.")]
+    InvalidUploadWindow,
+
+    #[msg("Timestamp is outside the upload window.")]
+    TimestampOutOfWindow,
+
+    #[msg("Batch close needs one epoch per account pair.")]
+    EpochCountMismatch,
+
+    #[msg("Account under the old seeds must be migrated first.")]
+    LegacySeedsNotMigrated,
+
+    #[msg("Too many dataset ids.")]
+    TooManyDatasets,
+
+    #[msg("Global consensus epoch is in the future or not after the last one.")]
+    InvalidGlobalEpoch,
+
+    #[msg("Merkle path is too long.")]
+    ProofTooLong,
+
+    #[msg("Account holds no consensus root.")]
+    InvalidRootAccount,
+
+    #[msg("The signer registry account is required.")]
+    SignerRegistryRequired,
+
+    #[msg("Fees cannot be withdrawn to the config account.")]
+    InvalidWithdrawDestination,
+
+    #[msg("Account is not the RewardMint PDA of the old mint.")]
+    InvalidRewardMintAccount,
+
+    #[msg("Deployment id cannot be 0.")]
+    InvalidDeployment,
+
+    #[msg("Merkle path is empty.")]
+    EmptyProof,
+
+    #[msg("Epoch start does not match the signed message.")]
+    EpochMismatch,
 }
@@ -0,0 +1,201 @@
+use crate::states::signer::TrustedSigner;
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct SignerRotated {
+    pub old_signer: TrustedSigner,
+    pub new_signer: TrustedSigner,
+    /// Slot from which `new_signer` is the trusted signer.
+    pub effective_slot: u64,
+}
+
+#[event]
+pub struct ConfigInitialized {
+    pub owner: Pubkey,
+    pub signer: TrustedSigner,
+    pub fee: u64,
+    pub deployment: u64,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub signer: TrustedSigner,
+    pub fee: u64,
+    pub signer_grace_slots: u64,
+    pub max_validity: i64,
+}
+
+#[event]
+pub struct OwnershipTransferProposed {
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,
+}
+
+#[event]
+pub struct OwnershipTransferCancelled {
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,
+}
+
+#[event]
+pub struct OwnershipTransferred {
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct SignerSet {
+    pub key: TrustedSigner,
+    pub enabled: bool,
+    pub valid_from: u64,
+    pub valid_until: u64,
+    pub scope: u8,
+}
+
+#[event]
+pub struct SignerRemoved {
+    pub key: TrustedSigner,
+}
+
+#[event]
+pub struct PauseSet {
+    pub by: Pubkey,
+    pub paused: u8,
+}
+
+#[event]
+pub struct MintPauseSet {
+    pub by: Pubkey,
+    pub mint: Pubkey,
+    pub paused: bool,
+}
+
+#[event]
+pub struct GuardianSet {
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct TransferFeeModeSet {
+    pub gross_up: bool,
+}
+
+#[event]
+pub struct MintLimitsSet {
+    pub mint: Pubkey,
+    pub max_per_claim: u64,
+    pub user_window_cap: u64,
+    pub epoch_cap: u64,
+}
+
+#[event]
+pub struct CosignPolicySet {
+    pub threshold: u64,
+    pub required: u8,
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    pub to: Pubkey,
+    pub amount: u64,
+    pub total_withdrawn: u64,
+}
+
+#[event]
+pub struct TokensWithdrawn {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ConsensusRetentionSet {
+    pub retention: i64,
+}
+
+#[event]
+pub struct ConsensusClosed {
+    pub user: Pubkey,
+    pub timestamp: u64,
+    pub consensus_proof: [u8; 32],
+    pub global: bool,
+    /// Archive record count and root after this record was added.
+    pub count: u64,
+    pub root: [u8; 32],
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub version: u8,
+}
+
+#[event]
+pub struct UploadWindowSet {
+    pub epoch_length: u64,
+    pub max_past: i64,
+    pub max_future: i64,
+}
+
+#[event]
+pub struct AccountMoved {
+    pub from: Pubkey,
+    pub to: Pubkey,
+}
+
+#[event]
+pub struct ConsensusUploaded {
+    pub user: Pubkey,
+    pub epoch: u64,
+    /// Start of `epoch`, in unix time.
+    pub timestamp: u64,
+    pub consensus_proof: [u8; 32],
+    /// Upload fee charged to `user`, in lamports.
+    pub fee: u64,
+    /// Key that signed the payload.
+    pub signer: TrustedSigner,
+}
+
+#[event]
+pub struct GlobalConsensusPublished {
+    pub epoch_start: u64,
+    pub epoch_length: u64,
+    pub root: [u8; 32],
+    pub contributors: u64,
+    pub dataset_ids: Vec<u64>,
+    pub prev_epoch_start: u64,
+    pub prev_root: [u8; 32],
+    /// Key that signed the payload.
+    pub signer: TrustedSigner,
+}
+
+#[event]
+pub struct BadgeIssued {
+    pub owner: Pubkey,
+    pub quiz: u64,
+    pub tier: u64,
+    /// Key that signed the payload.
+    pub signer: TrustedSigner,
+}
+
+#[event]
+pub struct RewardMintRegistered {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct RewardClaimed {
+    pub receiver: Pubkey,
+    pub task: u16,
+    pub nonce: u16,
+    pub mint: Pubkey,
+    /// Amount sent from the vault.
+    pub amount: u64,
+    /// Amount the receiver got after any transfer fee.
+    pub received: u64,
+    /// Key that signed the payload.
+    pub signer: TrustedSigner,
+}
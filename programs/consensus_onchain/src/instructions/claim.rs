@@ -1,34 +1,44 @@
 use crate::errors::error::ErrorCode;
+use crate::events::event::*;
+use crate::payloads::{self, ClaimInput, KIND_CLAIM};
 use crate::states::claim::*;
-use crate::states::consensus::Config;
+use crate::states::consensus::*;
+use crate::states::limits::*;
+use crate::states::profile::*;
+use crate::states::signer::*;
 use crate::utils;
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
-use serde::{Deserialize, Serialize};
-use solana_program::instruction::Instruction;
-use solana_program::sysvar::instructions::{
-    load_current_index_checked, load_instruction_at_checked, ID as IX_ID,
-};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use solana_program::sysvar::instructions::ID as IX_ID;
 
 #[derive(Accounts)]
-pub struct IniClaim<'info> {
+pub struct RegisterRewardMint<'info> {
     #[account(
         init_if_needed,
         seeds = [b"state".as_ref()],
         bump,
         payer = payer,
-        space = 8 + StateAccount::LEN
+        space = 8 + StateAccount::INIT_SPACE
     )]
     pub state: Box<Account<'info, StateAccount>>,
 
-    pub mint: Account<'info, Mint>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         constraint = token_vault.mint == mint.key() @ ErrorCode::InvalidTokenAccount,
         constraint = token_vault.owner == state.key() @ ErrorCode::InvalidVaultOwner,
     )]
-    pub token_vault: Account<'info, TokenAccount>,
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [b"reward_mint".as_ref(), mint.key().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + RewardMint::INIT_SPACE
+    )]
+    pub reward_mint: Box<Account<'info, RewardMint>>,
 
     #[account(
         mut,
@@ -44,21 +54,39 @@ pub struct IniClaim<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn ini_claim(ctx: Context<IniClaim>) -> Result<()> {
-    let state = &mut ctx.accounts.state;
-    state.token_mint = ctx.accounts.mint.key();
-    state.token_vault = ctx.accounts.token_vault.key();
-    state.bump = ctx.bumps.state;
+/// Register a reward mint and its vault, or change whether it can be claimed.
+/// Pointing a registered mint at another vault needs `force`.
+pub fn register_reward_mint(
+    ctx: Context<RegisterRewardMint>,
+    enabled: bool,
+    force: bool,
+) -> Result<()> {
+    if !ctx.accounts.config.initialized {
+        return Err(ErrorCode::NotInitialized.into());
+    }
+    let reward_mint = &mut ctx.accounts.reward_mint;
+    let vault = ctx.accounts.token_vault.key();
+    let unset = reward_mint.vault == Pubkey::default();
+    require!(
+        unset || reward_mint.vault == vault || force,
+        ErrorCode::VaultAlreadyConfigured
+    );
+    reward_mint.version = RewardMint::VERSION;
+    reward_mint.mint = ctx.accounts.mint.key();
+    reward_mint.vault = vault;
+    reward_mint.enabled = enabled;
+    reward_mint.bump = ctx.bumps.reward_mint;
+    reward_mint.authority_bump = ctx.bumps.state;
+    ctx.accounts.state.version = StateAccount::VERSION;
+    ctx.accounts.state.bump = ctx.bumps.state;
+    emit!(RewardMintRegistered {
+        mint: reward_mint.mint,
+        vault,
+        enabled,
+    });
     Ok(())
 }
 
-#[derive(Serialize, Deserialize)]
-struct ClaimInput {
-    pub task: u16,
-    pub nonce: u16,
-    pub reward: u64,
-    pub receiver: Pubkey,
-}
 #[derive(Accounts)]
 #[instruction(task:u16)]
 pub struct Claim<'info> {
@@ -80,18 +108,38 @@ pub struct Claim<'info> {
 
     #[account(
         init_if_needed,
-        seeds = [b"reward".as_ref(), format!("{}", task).as_ref(), payer.key().as_ref()],
+        seeds = [
+            b"reward".as_ref(),
+            task.to_le_bytes().as_ref(),
+            mint.key().as_ref(),
+            payer.key().as_ref(),
+        ],
         bump,
         payer = payer,
         space = 8 + ClaimReward::INIT_SPACE
     )]
     pub reward: Box<Account<'info, ClaimReward>>,
+    /// CHECK: Address of the same task under the old seeds, which must be
+    /// moved with `migrate_reward_seeds` first so its nonce carries over.
+    #[account(
+        seeds = [b"reward".as_ref(), format!("{}", task).as_ref(), payer.key().as_ref()],
+        bump,
+        constraint = !utils::holds_account::<ClaimReward>(&legacy_reward) @ ErrorCode::LegacySeedsNotMigrated,
+    )]
+    pub legacy_reward: UncheckedAccount<'info>,
     #[account(
         mut,
-        constraint = token_vault.mint == state.token_mint @ ErrorCode::InvalidTokenAccount,
-        constraint = token_vault.owner == state.key() @ ErrorCode::InvalidVaultOwner,
+        seeds = [b"reward_mint".as_ref(), mint.key().as_ref()],
+        bump = reward_mint.bump,
+        constraint = reward_mint.enabled @ ErrorCode::RewardMintDisabled,
+        constraint = !reward_mint.paused @ ErrorCode::Paused,
     )]
-    pub token_vault: Box<Account<'info, TokenAccount>>,
+    pub reward_mint: Box<Account<'info, RewardMint>>,
+    #[account(
+        mut,
+        address = reward_mint.vault @ ErrorCode::InvalidTokenAccount,
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
@@ -100,10 +148,42 @@ pub struct Claim<'info> {
         associated_token::authority = payer,
         associated_token::token_program = token_program,
         constraint = receiver.owner == payer.key() @ ErrorCode::InvalidTokenAccount,
-        constraint = receiver.mint == state.token_mint @ ErrorCode::InvalidTokenAccount,
+        constraint = receiver.mint == mint.key() @ ErrorCode::InvalidTokenAccount,
+    )]
+    pub receiver: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"limits".as_ref(), mint.key().as_ref()],
+        bump = limits.bump,
+    )]
+    pub limits: Box<Account<'info, MintLimits>>,
+    #[account(
+        init_if_needed,
+        seeds = [b"usage".as_ref(), mint.key().as_ref(), payer.key().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + UserUsage::INIT_SPACE
     )]
-    pub receiver: Box<Account<'info, TokenAccount>>,
-    pub mint: Box<Account<'info, Mint>>,
+    pub usage: Box<Account<'info, UserUsage>>,
+    #[account(
+        init_if_needed,
+        seeds = [b"profile".as_ref(), payer.key().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + ContributorProfile::INIT_SPACE
+    )]
+    pub profile: Box<Account<'info, ContributorProfile>>,
+    #[account(
+    seeds = [b"signers".as_ref()],
+    bump = signer_registry.bump,
+    )]
+    pub signer_registry: Option<Box<Account<'info, SignerRegistry>>>,
+    #[account(
+    seeds = [b"signer_history".as_ref()],
+    bump = signer_history.bump,
+    )]
+    pub signer_history: Option<Box<Account<'info, SignerHistory>>>,
     /// CHECK: The address check is needed because otherwise
     /// the supplied Sysvar could be anything else.
     /// The Instruction Sysvar has not been implemented
@@ -111,44 +191,65 @@ pub struct Claim<'info> {
     #[account(address = IX_ID)]
     pub ix_sysvar: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 pub fn claim(ctx: Context<Claim>, task: u16, msg: Vec<u8>, sig: [u8; 64]) -> Result<()> {
-    let msg_str = String::from_utf8(msg.clone()).map_err(|_| ErrorCode::MessageShouldString)?;
-
-    let claim_info: ClaimInput =
-        serde_json::from_str(&msg_str).map_err(|_| ErrorCode::MessageShouldJson)?;
-
-    msg!(
-        "task: {}, nonce: {}, reward: {}, receiver: {}",
-        claim_info.task,
-        claim_info.nonce,
-        claim_info.reward,
-        claim_info.receiver.to_string()
-    );
+    let claim_info: ClaimInput = payloads::decode(&msg)?;
+    // `task` seeds the reward PDA, so it must be the signed one.
+    require!(task == claim_info.task, ErrorCode::TaskMismatch);
 
     let owner = *ctx.accounts.payer.key;
     require!(owner == claim_info.receiver, ErrorCode::InvalidOwnerError);
+    require!(
+        ctx.accounts.mint.key() == claim_info.mint,
+        ErrorCode::MintMismatch
+    );
 
     let config_state = &ctx.accounts.config;
 
     if !config_state.initialized {
         return Err(ErrorCode::NotInitialized.into());
     }
-
-    let signer_key = config_state.signer.to_bytes();
-
-    // Get what should be the Ed25519Program instruction
-    let index = load_current_index_checked(&ctx.accounts.ix_sysvar)?;
-    let ix: Instruction = load_instruction_at_checked((index - 1).into(), &ctx.accounts.ix_sysvar)?;
-
-    // Check that ix is what we expect to have been sent
-    utils::verify_ed25519_ix(&ix, &signer_key, &msg, &sig)?;
+    config_state.check_not_paused(PAUSE_CLAIM)?;
+
+    claim_info
+        .domain
+        .check(KIND_CLAIM, config_state.deployment)?;
+    payloads::check_expiry(claim_info.expires_at, config_state.max_validity)?;
+
+    let signatures = utils::load_signatures(&ctx.accounts.ix_sysvar)?;
+    let signed_by = utils::verify_signer(
+        &signatures,
+        config_state,
+        ctx.accounts
+            .signer_registry
+            .as_deref()
+            .map(|registry| &**registry),
+        ctx.accounts
+            .signer_history
+            .as_deref()
+            .map(|history| &**history),
+        SCOPE_CLAIM,
+        &msg,
+        &sig,
+    )?;
+    utils::verify_cosigners(
+        &signatures,
+        config_state,
+        ctx.accounts
+            .signer_registry
+            .as_deref()
+            .map(|registry| &**registry),
+        SCOPE_CLAIM,
+        &msg,
+        Some(claim_info.reward),
+    )?;
 
     let reward_info = &mut ctx.accounts.reward;
 
+    reward_info.version = ClaimReward::VERSION;
     reward_info.owner = claim_info.receiver;
 
     reward_info.reward = claim_info.reward;
@@ -156,15 +257,42 @@ pub fn claim(ctx: Context<Claim>, task: u16, msg: Vec<u8>, sig: [u8; 64]) -> Res
     require!(reward_info.times == claim_info.nonce, ErrorCode::NonceError);
     reward_info.times += 1;
 
+    let clock = Clock::get()?;
+    let transfer = utils::reward_transfer(
+        &ctx.accounts.mint.to_account_info(),
+        claim_info.reward,
+        config_state.gross_up_transfer_fees,
+        clock.epoch,
+    )?;
+    reward_info.received = transfer.received;
+
+    ctx.accounts.usage.version = UserUsage::VERSION;
+    ctx.accounts.usage.bump = ctx.bumps.usage;
+    ctx.accounts.limits.record(
+        &mut ctx.accounts.usage,
+        transfer.sent,
+        clock.unix_timestamp,
+        clock.epoch,
+    )?;
+
+    let profile = &mut ctx.accounts.profile;
+    profile.version = ContributorProfile::VERSION;
+    profile.user = ctx.accounts.payer.key();
+    profile.bump = ctx.bumps.profile;
+    profile.record_claim(ctx.accounts.mint.key(), transfer.received);
+
+    let reward_mint = &mut ctx.accounts.reward_mint;
+    reward_mint.claimed = reward_mint.claimed.saturating_add(transfer.sent);
     let state = &mut ctx.accounts.state;
-    state.claimed += claim_info.reward;
+    state.claimed = state.claimed.saturating_add(transfer.sent);
     // transfer token
 
-    let seeds = &[b"state".as_ref(), &[state.bump]];
+    let seeds = &[b"state".as_ref(), &[reward_mint.authority_bump]];
     let signer = &[&seeds[..]];
     // Transfer tokens
-    let cpi_accounts = Transfer {
+    let cpi_accounts = TransferChecked {
         from: ctx.accounts.token_vault.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
         to: ctx.accounts.receiver.to_account_info(),
         authority: ctx.accounts.state.to_account_info(),
     };
@@ -172,58 +300,23 @@ pub fn claim(ctx: Context<Claim>, task: u16, msg: Vec<u8>, sig: [u8; 64]) -> Res
     let cpi_program = ctx.accounts.token_program.to_account_info();
     let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
 
-    token::transfer(cpi_ctx, claim_info.reward)?;
-
-    Ok(())
-}
-
-#[derive(Accounts)]
-pub struct IniClaimPublic<'info> {
-    #[account(
-        init_if_needed,
-        seeds = [b"state".as_ref()],
-        bump,
-        payer = payer,
-        space = 8 + StateAccount::LEN
-    )]
-    pub state: Box<Account<'info, StateAccount>>,
+    token_interface::transfer_checked(cpi_ctx, transfer.sent, ctx.accounts.mint.decimals)?;
 
-    pub mint: Account<'info, Mint>,
-
-    #[account(
-        constraint = token_vault.mint == mint.key() @ ErrorCode::InvalidTokenAccount,
-        constraint = token_vault.owner == state.key() @ ErrorCode::InvalidVaultOwner,
-    )]
-    pub token_vault: Account<'info, TokenAccount>,
-
-    #[account(
-        mut,
-        seeds = [b"config".as_ref()],
-        bump,
-    )]
-    pub config: Box<Account<'info, Config>>,
-    #[account(
-        mut,
-        constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
-    )]
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    emit!(RewardClaimed {
+        receiver: claim_info.receiver,
+        task: claim_info.task,
+        nonce: claim_info.nonce,
+        mint: ctx.accounts.mint.key(),
+        amount: transfer.sent,
+        received: transfer.received,
+        signer: signed_by,
+    });
 
-pub fn ini_claim_public(ctx: Context<IniClaimPublic>) -> Result<()> {
-    let state = &mut ctx.accounts.state;
-    state.public_token_mint = ctx.accounts.mint.key();
-    state.public_token_vault = ctx.accounts.token_vault.key();
-    state.bump = ctx.bumps.state;
     Ok(())
 }
 
 #[derive(Accounts)]
-#[instruction(task:u16)]
-pub struct ClaimPublic<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-
+pub struct SetTransferFeeMode<'info> {
     #[account(
     mut,
     seeds = [b"config".as_ref()],
@@ -231,112 +324,18 @@ pub struct ClaimPublic<'info> {
     )]
     pub config: Box<Account<'info, Config>>,
     #[account(
-        mut,
-        seeds = [b"state".as_ref()],
-        bump,
-    )]
-    pub state: Box<Account<'info, StateAccount>>,
-
-    #[account(
-        init_if_needed,
-        seeds = [b"reward".as_ref(), format!("{}", task).as_ref(), payer.key().as_ref()],
-        bump,
-        payer = payer,
-        space = 8 + ClaimReward::INIT_SPACE
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
     )]
-    pub reward: Box<Account<'info, ClaimReward>>,
-    #[account(
-        mut,
-        constraint = token_vault.mint == state.public_token_mint @ ErrorCode::InvalidTokenAccount,
-        constraint = token_vault.owner == state.key() @ ErrorCode::InvalidVaultOwner,
-    )]
-    pub token_vault: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        init_if_needed,
-        payer = payer,
-        associated_token::mint = mint,
-        associated_token::authority = payer,
-        associated_token::token_program = token_program,
-        constraint = receiver.owner == payer.key() @ ErrorCode::InvalidTokenAccount,
-        constraint = receiver.mint == state.public_token_mint @ ErrorCode::InvalidTokenAccount,
-    )]
-    pub receiver: Box<Account<'info, TokenAccount>>,
-    pub mint: Box<Account<'info, Mint>>,
-    /// CHECK: The address check is needed because otherwise
-    /// the supplied Sysvar could be anything else.
-    /// The Instruction Sysvar has not been implemented
-    /// in the Anchor framework yet, so this is the safe approach.
-    #[account(address = IX_ID)]
-    pub ix_sysvar: AccountInfo<'info>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub payer: Signer<'info>,
 }
 
-pub fn claim_public(
-    ctx: Context<ClaimPublic>,
-    task: u16,
-    msg: Vec<u8>,
-    sig: [u8; 64],
-) -> Result<()> {
-    let msg_str = String::from_utf8(msg.clone()).map_err(|_| ErrorCode::MessageShouldString)?;
-
-    let claim_info: ClaimInput =
-        serde_json::from_str(&msg_str).map_err(|_| ErrorCode::MessageShouldJson)?;
-
-    msg!(
-        "task: {}, nonce: {}, reward: {}, receiver: {}",
-        claim_info.task,
-        claim_info.nonce,
-        claim_info.reward,
-        claim_info.receiver.to_string()
-    );
-
-    let owner = *ctx.accounts.payer.key;
-    require!(owner == claim_info.receiver, ErrorCode::InvalidOwnerError);
-
-    let config_state = &ctx.accounts.config;
-
+/// Choose whether claims gross up transfer fees or pay the reward net of them.
+pub fn set_transfer_fee_mode(ctx: Context<SetTransferFeeMode>, gross_up: bool) -> Result<()> {
+    let config_state = &mut ctx.accounts.config;
     if !config_state.initialized {
         return Err(ErrorCode::NotInitialized.into());
     }
-
-    let signer_key = config_state.signer.to_bytes();
-
-    // Get what should be the Ed25519Program instruction
-    let index = load_current_index_checked(&ctx.accounts.ix_sysvar)?;
-    let ix: Instruction = load_instruction_at_checked((index - 1).into(), &ctx.accounts.ix_sysvar)?;
-
-    // Check that ix is what we expect to have been sent
-    utils::verify_ed25519_ix(&ix, &signer_key, &msg, &sig)?;
-
-    let reward_info = &mut ctx.accounts.reward;
-
-    reward_info.owner = claim_info.receiver;
-
-    reward_info.reward = claim_info.reward;
-
-    require!(reward_info.times == claim_info.nonce, ErrorCode::NonceError);
-    reward_info.times += 1;
-
-    let state = &mut ctx.accounts.state;
-    state.claimed += claim_info.reward;
-    // transfer token
-
-    let seeds = &[b"state".as_ref(), &[state.bump]];
-    let signer = &[&seeds[..]];
-    // Transfer tokens
-    let cpi_accounts = Transfer {
-        from: ctx.accounts.token_vault.to_account_info(),
-        to: ctx.accounts.receiver.to_account_info(),
-        authority: ctx.accounts.state.to_account_info(),
-    };
-
-    let cpi_program = ctx.accounts.token_program.to_account_info();
-    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-
-    token::transfer(cpi_ctx, claim_info.reward)?;
-
+    config_state.gross_up_transfer_fees = gross_up;
+    emit!(TransferFeeModeSet { gross_up });
     Ok(())
 }
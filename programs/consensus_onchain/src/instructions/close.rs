@@ -0,0 +1,209 @@
+use crate::errors::error::ErrorCode;
+use crate::events::event::*;
+use crate::states::consensus::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetConsensusRetention<'info> {
+    #[account(
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+    init_if_needed,
+    seeds = [b"consensus_archive".as_ref()],
+    bump,
+    payer = payer,
+    space = 8 + ConsensusArchive::INIT_SPACE
+    )]
+    pub archive: Box<Account<'info, ConsensusArchive>>,
+    #[account(
+    init_if_needed,
+    seeds = [b"upload_window".as_ref()],
+    bump,
+    payer = payer,
+    space = 8 + UploadWindow::INIT_SPACE
+    )]
+    pub upload_window: Box<Account<'info, UploadWindow>>,
+    #[account(mut,
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
+    )]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Set how long consensus data must stay on-chain before it can be closed.
+/// Closing is impossible until this has been called once. The retention
+/// must cover `UploadWindow::min_retention`, or a closed upload could be
+/// replayed into a fresh account.
+pub fn set_consensus_retention(ctx: Context<SetConsensusRetention>, retention: i64) -> Result<()> {
+    if !ctx.accounts.config.initialized {
+        return Err(ErrorCode::NotInitialized.into());
+    }
+    let upload_window = &mut ctx.accounts.upload_window;
+    upload_window.init_defaults(ctx.bumps.upload_window);
+    require!(
+        retention >= upload_window.min_retention(),
+        ErrorCode::InvalidRetention
+    );
+    let archive = &mut ctx.accounts.archive;
+    archive.version = ConsensusArchive::VERSION;
+    archive.retention = retention;
+    archive.bump = ctx.bumps.archive;
+    emit!(ConsensusRetentionSet { retention });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(epoch:u64)]
+pub struct CloseConsensus<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+    mut,
+    seeds = [b"consensus".as_ref(), epoch.to_le_bytes().as_ref(), user.key().as_ref()],
+    bump,
+    close = user,
+    )]
+    pub consensus: Box<Account<'info, ConsensusState>>,
+    #[account(
+    mut,
+    seeds = [b"consensus_archive".as_ref()],
+    bump = archive.bump,
+    )]
+    pub archive: Box<Account<'info, ConsensusArchive>>,
+    #[account(
+    seeds = [b"upload_window".as_ref()],
+    bump = upload_window.bump,
+    )]
+    pub upload_window: Box<Account<'info, UploadWindow>>,
+}
+
+/// Close one of the caller's `ConsensusState` accounts past retention and
+/// take its rent back. Accounts under the old seeds are moved with
+/// `migrate_consensus_seeds` first.
+pub fn close_consensus(ctx: Context<CloseConsensus>, _epoch: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let min_retention = ctx.accounts.upload_window.min_retention();
+    let archive = &mut ctx.accounts.archive;
+    archive.check_retention(&ctx.accounts.consensus, now, min_retention)?;
+    archive.append(ctx.accounts.user.key, &ctx.accounts.consensus);
+    emit_closed(archive, ctx.accounts.user.key(), &ctx.accounts.consensus);
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(key:u64)]
+pub struct CloseLegacyConsensus<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+    mut,
+    seeds = [format!("{}", key).as_ref(), user.key().as_ref()],
+    bump,
+    close = user,
+    )]
+    pub consensus: Box<Account<'info, ConsensusState>>,
+    #[account(
+    mut,
+    seeds = [b"consensus_archive".as_ref()],
+    bump = archive.bump,
+    )]
+    pub archive: Box<Account<'info, ConsensusArchive>>,
+    #[account(
+    seeds = [b"upload_window".as_ref()],
+    bump = upload_window.bump,
+    )]
+    pub upload_window: Box<Account<'info, UploadWindow>>,
+}
+
+/// Close a `ConsensusState` still under the old seeds `[key, user]`, such as
+/// one `migrate_consensus_seeds` cannot move because its epoch already has
+/// an account under the new seeds. Records written before the version byte
+/// must go through `migrate_consensus` first; until then they fail to
+/// deserialize.
+pub fn close_legacy_consensus(ctx: Context<CloseLegacyConsensus>, _key: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let min_retention = ctx.accounts.upload_window.min_retention();
+    let archive = &mut ctx.accounts.archive;
+    archive.check_retention(&ctx.accounts.consensus, now, min_retention)?;
+    archive.append(ctx.accounts.user.key, &ctx.accounts.consensus);
+    emit_closed(archive, ctx.accounts.user.key(), &ctx.accounts.consensus);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseConsensusBatch<'info> {
+    #[account(
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+    mut,
+    seeds = [b"consensus_archive".as_ref()],
+    bump = archive.bump,
+    )]
+    pub archive: Box<Account<'info, ConsensusArchive>>,
+    #[account(
+    seeds = [b"upload_window".as_ref()],
+    bump = upload_window.bump,
+    )]
+    pub upload_window: Box<Account<'info, UploadWindow>>,
+    #[account(
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
+    )]
+    pub payer: Signer<'info>,
+}
+
+/// Close `ConsensusState` accounts past retention on behalf of their
+/// uploaders. `remaining_accounts` holds `(consensus, uploader)` pairs and
+/// `epochs` the epoch of each pair, as in `close_consensus`; the rent goes
+/// back to each uploader.
+pub fn close_consensus_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CloseConsensusBatch<'info>>,
+    epochs: Vec<u64>,
+) -> Result<()> {
+    let pairs = ctx.remaining_accounts.chunks_exact(2);
+    require!(
+        pairs.len() > 0 && pairs.remainder().is_empty(),
+        ErrorCode::InvalidCloseBatch
+    );
+    require!(pairs.len() == epochs.len(), ErrorCode::EpochCountMismatch);
+    let now = Clock::get()?.unix_timestamp;
+    let min_retention = ctx.accounts.upload_window.min_retention();
+    let archive = &mut ctx.accounts.archive;
+
+    for (pair, epoch) in pairs.zip(epochs) {
+        let (consensus_info, uploader) = (&pair[0], &pair[1]);
+        require!(uploader.is_writable, ErrorCode::InvalidCloseBatch);
+        let consensus = Account::<ConsensusState>::try_from(consensus_info)?;
+        let (expected, _) = Pubkey::find_program_address(
+            &[
+                b"consensus".as_ref(),
+                epoch.to_le_bytes().as_ref(),
+                uploader.key.as_ref(),
+            ],
+            ctx.program_id,
+        );
+        require_keys_eq!(expected, consensus_info.key(), ErrorCode::InvalidCloseBatch);
+
+        archive.check_retention(&consensus, now, min_retention)?;
+        archive.append(uploader.key, &consensus);
+        emit_closed(archive, uploader.key(), &consensus);
+        consensus.close(uploader.clone())?;
+    }
+    Ok(())
+}
+
+fn emit_closed(archive: &ConsensusArchive, user: Pubkey, state: &ConsensusState) {
+    emit!(ConsensusClosed {
+        user,
+        timestamp: state.timestamp,
+        consensus_proof: state.consensus_proof,
+        global: state.global,
+        count: archive.count,
+        root: archive.root,
+    });
+}
@@ -0,0 +1,167 @@
+use crate::errors::error::ErrorCode;
+use crate::events::event::*;
+use crate::payloads::{self, GlobalRollup, KIND_GLOBAL};
+use crate::states::consensus::*;
+use crate::states::global::*;
+use crate::states::signer::*;
+use crate::utils;
+use anchor_lang::prelude::*;
+use solana_program::sysvar::instructions::ID as IX_ID;
+
+#[derive(Accounts)]
+#[instruction(epoch_start:u64)]
+pub struct PublishGlobalConsensus<'info> {
+    #[account(
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    /// Pays rent; authorization comes from the signed rollup.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+    init_if_needed,
+    seeds = [b"upload_window".as_ref()],
+    bump,
+    payer = payer,
+    space = 8 + UploadWindow::INIT_SPACE
+    )]
+    pub upload_window: Box<Account<'info, UploadWindow>>,
+    #[account(
+    init_if_needed,
+    seeds = [b"global_head".as_ref()],
+    bump,
+    payer = payer,
+    space = 8 + GlobalConsensusHead::INIT_SPACE
+    )]
+    pub head: Box<Account<'info, GlobalConsensusHead>>,
+    #[account(
+    init,
+    seeds = [b"global".as_ref(), epoch_start.to_le_bytes().as_ref()],
+    bump,
+    payer = payer,
+    space = 8 + GlobalConsensus::INIT_SPACE
+    )]
+    pub global: Box<Account<'info, GlobalConsensus>>,
+    #[account(
+    seeds = [b"signers".as_ref()],
+    bump = signer_registry.bump,
+    )]
+    pub signer_registry: Option<Box<Account<'info, SignerRegistry>>>,
+    #[account(
+    seeds = [b"signer_history".as_ref()],
+    bump = signer_history.bump,
+    )]
+    pub signer_history: Option<Box<Account<'info, SignerHistory>>>,
+    /// CHECK: The address check is needed because otherwise
+    /// the supplied Sysvar could be anything else.
+    /// The Instruction Sysvar has not been implemented
+    /// in the Anchor framework yet, so this is the safe approach.
+    #[account(address = IX_ID)]
+    pub ix_sysvar: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Publish the rollup of an upload epoch, signed like any other payload
+/// with `SCOPE_GLOBAL` and always subject to the co-signing count.
+/// Epochs are published in order of their start time, each linking to the
+/// root of the one before, and only once the epoch is over.
+pub fn publish_global_consensus(
+    ctx: Context<PublishGlobalConsensus>,
+    epoch_start: u64,
+    msg: Vec<u8>,
+    sig: [u8; 64],
+) -> Result<()> {
+    let rollup: GlobalRollup = payloads::decode(&msg)?;
+    // `epoch_start` seeds the global PDA, so it must be the signed one.
+    require!(epoch_start == rollup.epoch_start, ErrorCode::EpochMismatch);
+
+    let config_state = &ctx.accounts.config;
+    if !config_state.initialized {
+        return Err(ErrorCode::NotInitialized.into());
+    }
+    config_state.check_not_paused(PAUSE_GLOBAL)?;
+
+    rollup.domain.check(KIND_GLOBAL, config_state.deployment)?;
+    payloads::check_expiry(rollup.expires_at, config_state.max_validity)?;
+    require!(
+        rollup.dataset_ids.len() <= MAX_DATASETS,
+        ErrorCode::TooManyDatasets
+    );
+
+    let signatures = utils::load_signatures(&ctx.accounts.ix_sysvar)?;
+    let signer = utils::verify_signer(
+        &signatures,
+        config_state,
+        ctx.accounts
+            .signer_registry
+            .as_deref()
+            .map(|registry| &**registry),
+        ctx.accounts
+            .signer_history
+            .as_deref()
+            .map(|history| &**history),
+        SCOPE_GLOBAL,
+        &msg,
+        &sig,
+    )?;
+    utils::verify_cosigners(
+        &signatures,
+        config_state,
+        ctx.accounts
+            .signer_registry
+            .as_deref()
+            .map(|registry| &**registry),
+        SCOPE_GLOBAL,
+        &msg,
+        None,
+    )?;
+
+    let clock = Clock::get()?;
+    let upload_window = &mut ctx.accounts.upload_window;
+    upload_window.init_defaults(ctx.bumps.upload_window);
+    let now = u64::try_from(clock.unix_timestamp).map_err(|_| ErrorCode::InvalidGlobalEpoch)?;
+    let epoch = upload_window.epoch(epoch_start);
+    // Only a completed epoch has a final rollup.
+    let epoch_end = epoch_start.saturating_add(upload_window.epoch_length);
+    require!(
+        epoch * upload_window.epoch_length == epoch_start && epoch_end <= now,
+        ErrorCode::InvalidGlobalEpoch
+    );
+
+    let head = &mut ctx.accounts.head;
+    require!(
+        head.count == 0 || epoch_start > head.epoch_start,
+        ErrorCode::InvalidGlobalEpoch
+    );
+
+    let global = &mut ctx.accounts.global;
+    global.version = GlobalConsensus::VERSION;
+    global.epoch_start = epoch_start;
+    global.epoch_length = upload_window.epoch_length;
+    global.root = rollup.root;
+    global.contributors = rollup.contributors;
+    global.dataset_ids = rollup.dataset_ids;
+    global.prev_epoch_start = head.epoch_start;
+    global.prev_root = head.root;
+    global.published_at = clock.unix_timestamp;
+    global.bump = ctx.bumps.global;
+
+    head.version = GlobalConsensusHead::VERSION;
+    head.epoch_start = epoch_start;
+    head.root = global.root;
+    head.count += 1;
+    head.bump = ctx.bumps.head;
+
+    emit!(GlobalConsensusPublished {
+        epoch_start,
+        epoch_length: global.epoch_length,
+        root: global.root,
+        contributors: global.contributors,
+        dataset_ids: global.dataset_ids.clone(),
+        prev_epoch_start: global.prev_epoch_start,
+        prev_root: global.prev_root,
+        signer,
+    });
+    Ok(())
+}
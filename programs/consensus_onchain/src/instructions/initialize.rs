@@ -1,5 +1,8 @@
 use crate::errors::error::ErrorCode;
+use crate::events::event::*;
+use crate::program::ConsensusOnchain;
 use crate::states::consensus::*;
+use crate::states::signer::TrustedSigner;
 use anchor_lang::prelude::*;
 #[derive(Accounts)]
 pub struct Initialize<'info> {
@@ -13,17 +16,43 @@ pub struct Initialize<'info> {
     pub config: Box<Account<'info, Config>>,
     #[account(mut)]
     pub payer: Signer<'info>,
+    #[account(
+    constraint = program.programdata_address()? == Some(program_data.key()) @ ErrorCode::AccountError,
+    )]
+    pub program: Program<'info, ConsensusOnchain>,
+    /// Only the upgrade authority of the deployed program may initialize,
+    /// otherwise anyone could front-run the deployer and pick the signer.
+    #[account(
+    constraint = program_data.upgrade_authority_address == Some(payer.key()) @ ErrorCode::OnlyUpgradeAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn initialize(ctx: Context<Initialize>, signer: Pubkey, fee: u64) -> Result<()> {
+pub fn initialize(
+    ctx: Context<Initialize>,
+    signer: TrustedSigner,
+    fee: u64,
+    deployment: u64,
+) -> Result<()> {
     let config_state = &mut ctx.accounts.config;
     if config_state.initialized {
         return Err(ErrorCode::Initialized.into());
     }
+    require!(deployment != 0, ErrorCode::InvalidDeployment);
+    config_state.version = Config::VERSION;
     config_state.owner = *ctx.accounts.payer.key;
     config_state.signer = signer;
+    config_state.signer_since = Clock::get()?.slot;
     config_state.fee = fee;
+    config_state.deployment = deployment;
+    config_state.cosign_required = 1;
     config_state.initialized = true;
+    emit!(ConfigInitialized {
+        owner: config_state.owner,
+        signer,
+        fee,
+        deployment,
+    });
     Ok(())
 }
@@ -0,0 +1,55 @@
+use crate::errors::error::ErrorCode;
+use crate::events::event::*;
+use crate::states::consensus::*;
+use crate::states::limits::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+#[derive(Accounts)]
+pub struct SetMintLimits<'info> {
+    #[account(
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+    init_if_needed,
+    seeds = [b"limits".as_ref(), mint.key().as_ref()],
+    bump,
+    payer = payer,
+    space = 8 + MintLimits::INIT_SPACE
+    )]
+    pub limits: Box<Account<'info, MintLimits>>,
+    #[account(mut,
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
+    )]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Set the claim limits of a reward mint. Usage counters are kept.
+pub fn set_mint_limits(
+    ctx: Context<SetMintLimits>,
+    max_per_claim: u64,
+    user_window_cap: u64,
+    epoch_cap: u64,
+) -> Result<()> {
+    if !ctx.accounts.config.initialized {
+        return Err(ErrorCode::NotInitialized.into());
+    }
+    let limits = &mut ctx.accounts.limits;
+    limits.version = MintLimits::VERSION;
+    limits.mint = ctx.accounts.mint.key();
+    limits.max_per_claim = max_per_claim;
+    limits.user_window_cap = user_window_cap;
+    limits.epoch_cap = epoch_cap;
+    limits.bump = ctx.bumps.limits;
+    emit!(MintLimitsSet {
+        mint: limits.mint,
+        max_per_claim,
+        user_window_cap,
+        epoch_cap,
+    });
+    Ok(())
+}
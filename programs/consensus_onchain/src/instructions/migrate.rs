@@ -0,0 +1,568 @@
+use crate::errors::error::ErrorCode;
+use crate::events::event::*;
+use crate::states::badge::*;
+use crate::states::claim::*;
+use crate::states::consensus::*;
+use crate::states::legacy::*;
+use crate::states::signer::TrustedSigner;
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    /// CHECK: Still in the old layout, checked by `migrate`.
+    #[account(
+    mut,
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Upgrade `Config` to the current layout. Only the owner may do this, since
+/// the new fields start from their defaults. `deployment` is the id signed
+/// payloads must carry from now on (see `initialize`); it cannot be 0.
+pub fn migrate_config(ctx: Context<MigrateConfig>, deployment: u64) -> Result<()> {
+    require!(deployment != 0, ErrorCode::InvalidDeployment);
+    let payer = ctx.accounts.payer.key();
+    let bump = ctx.bumps.config;
+    let slot = Clock::get()?.slot;
+    migrate::<ConfigV0, _>(
+        &ctx.accounts.config,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        |version, mut data| match version {
+            0 => {
+                let old = ConfigV0::deserialize(&mut data)?;
+                require!(old.owner == payer, ErrorCode::OnlyOwner);
+                Ok(Config {
+                    version: Config::VERSION,
+                    signer: TrustedSigner::Ed25519(old.signer),
+                    owner: old.owner,
+                    fee: old.fee,
+                    initialized: old.initialized,
+                    bump,
+                    pending_owner: Pubkey::default(),
+                    signer_since: slot,
+                    signer_grace_slots: 0,
+                    deployment,
+                    max_validity: 0,
+                    cosign_threshold: 0,
+                    cosign_required: 1,
+                    paused: 0,
+                    guardian: Pubkey::default(),
+                    fees_collected: 0,
+                    total_withdrawn: 0,
+                    gross_up_transfer_fees: false,
+                    signer_registry_active: false,
+                })
+            }
+            _ => err!(ErrorCode::UnknownAccountLayout),
+        },
+    )
+}
+
+#[derive(Accounts)]
+pub struct MigrateState<'info> {
+    #[account(
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    /// CHECK: Still in the old layout, checked by `migrate`.
+    #[account(
+    mut,
+    seeds = [b"state".as_ref()],
+    bump,
+    )]
+    pub state: UncheckedAccount<'info>,
+    /// CHECK: `RewardMint` PDA of the old USDT mint, checked and created by
+    /// `migrate_state`.
+    #[account(mut)]
+    pub token_reward_mint: UncheckedAccount<'info>,
+    /// CHECK: `RewardMint` PDA of the old PUBLIC mint, checked and created by
+    /// `migrate_state`.
+    #[account(mut)]
+    pub public_reward_mint: UncheckedAccount<'info>,
+    #[account(mut,
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
+    )]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Upgrade `StateAccount` from its fixed-slot layout, and register the old
+/// USDT and PUBLIC mints as `RewardMint`s with their existing vaults. A mint
+/// registered already, or a slot that was never set, is left alone. Rent freed
+/// by the smaller layout is refunded to the owner.
+pub fn migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+    let bump = ctx.bumps.state;
+    let mut slots = Vec::new();
+    migrate::<StateAccountV0, _>(
+        &ctx.accounts.state,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        |version, mut data| match version {
+            0 => {
+                let old = StateAccountV0::deserialize(&mut data)?;
+                slots.push((old.token_mint, old.token_vault));
+                slots.push((old.public_token_mint, old.public_token_vault));
+                Ok(StateAccount {
+                    version: StateAccount::VERSION,
+                    bump,
+                    claimed: old.claimed,
+                })
+            }
+            _ => err!(ErrorCode::UnknownAccountLayout),
+        },
+    )?;
+
+    // The new layout is much smaller; hand the freed rent back to the owner.
+    let state_info = ctx.accounts.state.to_account_info();
+    let rent_exempt = Rent::get()?.minimum_balance(state_info.data_len());
+    let excess = state_info.lamports().saturating_sub(rent_exempt);
+    if excess > 0 {
+        **state_info.try_borrow_mut_lamports()? -= excess;
+        **ctx
+            .accounts
+            .payer
+            .to_account_info()
+            .try_borrow_mut_lamports()? += excess;
+    }
+
+    let accounts = [
+        &ctx.accounts.token_reward_mint,
+        &ctx.accounts.public_reward_mint,
+    ];
+    for (account, (mint, vault)) in accounts.into_iter().zip(slots) {
+        if mint == Pubkey::default() {
+            continue;
+        }
+        let (address, reward_bump) =
+            Pubkey::find_program_address(&[b"reward_mint".as_ref(), mint.as_ref()], &crate::ID);
+        require_keys_eq!(account.key(), address, ErrorCode::InvalidRewardMintAccount);
+        if !account.data_is_empty() {
+            continue;
+        }
+        create_pda(
+            account,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + RewardMint::INIT_SPACE,
+            &[b"reward_mint".as_ref(), mint.as_ref(), &[reward_bump]],
+        )?;
+        let reward_mint = RewardMint {
+            version: RewardMint::VERSION,
+            mint,
+            vault,
+            authority_bump: bump,
+            enabled: true,
+            paused: false,
+            claimed: 0,
+            withdrawn: 0,
+            bump: reward_bump,
+        };
+        reward_mint.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+        emit!(RewardMintRegistered {
+            mint,
+            vault,
+            enabled: true,
+        });
+    }
+    Ok(())
+}
+
+/// Accounts that anyone may upgrade: migration only copies their data.
+#[derive(Accounts)]
+pub struct Migrate<'info> {
+    /// CHECK: Still in the old layout, checked by `migrate`.
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn migrate_consensus(ctx: Context<Migrate>) -> Result<()> {
+    migrate::<ConsensusStateV0, _>(
+        &ctx.accounts.account,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        |version, mut data| match version {
+            0 => {
+                let old = ConsensusStateV0::deserialize(&mut data)?;
+                Ok(ConsensusState {
+                    version: ConsensusState::VERSION,
+                    global: old.global,
+                    timestamp: old.timestamp,
+                    consensus_proof: old.consensus_proof,
+                    bump: old.bump,
+                })
+            }
+            _ => err!(ErrorCode::UnknownAccountLayout),
+        },
+    )
+}
+
+pub fn migrate_claim_reward(ctx: Context<Migrate>) -> Result<()> {
+    migrate::<ClaimRewardV0, _>(
+        &ctx.accounts.account,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        |version, mut data| match version {
+            0 => {
+                let old = ClaimRewardV0::deserialize(&mut data)?;
+                Ok(ClaimReward {
+                    version: ClaimReward::VERSION,
+                    owner: old.owner,
+                    reward: old.reward,
+                    times: old.times,
+                    // Legacy claims were plain SPL Token transfers, without fees.
+                    received: old.reward,
+                })
+            }
+            _ => err!(ErrorCode::UnknownAccountLayout),
+        },
+    )
+}
+
+pub fn migrate_badge(ctx: Context<Migrate>) -> Result<()> {
+    migrate::<BadgeV0, _>(
+        &ctx.accounts.account,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        |version, mut data| match version {
+            0 => {
+                let old = BadgeV0::deserialize(&mut data)?;
+                Ok(Badge {
+                    version: Badge::VERSION,
+                    owner: old.owner,
+                    quiz: old.quiz,
+                    tier: old.tier,
+                })
+            }
+            _ => err!(ErrorCode::UnknownAccountLayout),
+        },
+    )
+}
+
+pub fn migrate_badge_config(ctx: Context<Migrate>) -> Result<()> {
+    migrate::<BadgeConfigV0, _>(
+        &ctx.accounts.account,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        |version, mut data| match version {
+            0 => {
+                let old = BadgeConfigV0::deserialize(&mut data)?;
+                Ok(BadgeConfig {
+                    version: BadgeConfig::VERSION,
+                    quiz: old.quiz,
+                    total: old.total,
+                })
+            }
+            _ => err!(ErrorCode::UnknownAccountLayout),
+        },
+    )
+}
+
+pub fn migrate_badge_config_pool(ctx: Context<Migrate>) -> Result<()> {
+    migrate::<BadgeConfigPoolV0, _>(
+        &ctx.accounts.account,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        |version, mut data| match version {
+            0 => {
+                let old = BadgeConfigPoolV0::deserialize(&mut data)?;
+                Ok(BadgeConfigPool {
+                    version: BadgeConfigPool::VERSION,
+                    total: old.total,
+                    config_count: old.config_count,
+                })
+            }
+            _ => err!(ErrorCode::UnknownAccountLayout),
+        },
+    )
+}
+
+#[derive(Accounts)]
+#[instruction(key:u64)]
+pub struct MigrateConsensusSeeds<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+    init_if_needed,
+    seeds = [b"upload_window".as_ref()],
+    bump,
+    payer = user,
+    space = 8 + UploadWindow::INIT_SPACE
+    )]
+    pub upload_window: Box<Account<'info, UploadWindow>>,
+    #[account(
+    mut,
+    seeds = [format!("{}", key).as_ref(), user.key().as_ref()],
+    bump,
+    close = user,
+    )]
+    pub legacy: Box<Account<'info, ConsensusState>>,
+    #[account(
+    init,
+    seeds = [
+        b"consensus".as_ref(),
+        upload_window.epoch(legacy.timestamp).to_le_bytes().as_ref(),
+        user.key().as_ref(),
+    ],
+    bump,
+    payer = user,
+    space = 8 + ConsensusState::INIT_SPACE
+    )]
+    pub consensus: Box<Account<'info, ConsensusState>>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Move a `ConsensusState` from the seeds `[key, user]`, with `key` the
+/// decimal timestamp it was created for, to the namespaced seeds of its
+/// epoch, normalizing the timestamp to the epoch start as uploads do. Where
+/// the epoch already has an account, from a second old account or a new
+/// upload, the old one stays where it is and can be closed with
+/// `close_legacy_consensus`. The old account must already be in the current
+/// layout, so records without a version byte go through `migrate_consensus`
+/// first.
+pub fn migrate_consensus_seeds(ctx: Context<MigrateConsensusSeeds>, _key: u64) -> Result<()> {
+    ctx.accounts
+        .upload_window
+        .init_defaults(ctx.bumps.upload_window);
+    let upload_window = &ctx.accounts.upload_window;
+    let legacy = &ctx.accounts.legacy;
+    let epoch = upload_window.epoch(legacy.timestamp);
+    let consensus = &mut ctx.accounts.consensus;
+    consensus.version = ConsensusState::VERSION;
+    consensus.global = legacy.global;
+    consensus.timestamp = epoch * upload_window.epoch_length;
+    consensus.consensus_proof = legacy.consensus_proof;
+    consensus.bump = ctx.bumps.consensus;
+    emit!(AccountMoved {
+        from: legacy.key(),
+        to: consensus.key(),
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(quiz:u64)]
+pub struct MigrateBadgeSeeds<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+    mut,
+    seeds = [format!("{}", quiz).as_ref(), user.key().as_ref()],
+    bump,
+    close = user,
+    )]
+    pub legacy: Box<Account<'info, Badge>>,
+    #[account(
+    init,
+    seeds = [b"badge".as_ref(), quiz.to_le_bytes().as_ref(), user.key().as_ref()],
+    bump,
+    payer = user,
+    space = 8 + Badge::INIT_SPACE
+    )]
+    pub badge: Box<Account<'info, Badge>>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Move a `Badge` from the seeds `[quiz, user]` to the namespaced ones.
+pub fn migrate_badge_seeds(ctx: Context<MigrateBadgeSeeds>, _quiz: u64) -> Result<()> {
+    let legacy = &ctx.accounts.legacy;
+    let badge = &mut ctx.accounts.badge;
+    badge.version = Badge::VERSION;
+    badge.owner = legacy.owner;
+    badge.quiz = legacy.quiz;
+    badge.tier = legacy.tier;
+    emit!(AccountMoved {
+        from: legacy.key(),
+        to: badge.key(),
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(task:u16)]
+pub struct MigrateRewardSeeds<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+    mut,
+    seeds = [b"reward".as_ref(), format!("{}", task).as_ref(), user.key().as_ref()],
+    bump,
+    close = user,
+    )]
+    pub legacy: Box<Account<'info, ClaimReward>>,
+    #[account(
+    seeds = [b"reward_mint".as_ref(), reward_mint.mint.as_ref()],
+    bump = reward_mint.bump,
+    )]
+    pub reward_mint: Box<Account<'info, RewardMint>>,
+    #[account(
+    init,
+    seeds = [
+        b"reward".as_ref(),
+        task.to_le_bytes().as_ref(),
+        reward_mint.mint.as_ref(),
+        user.key().as_ref(),
+    ],
+    bump,
+    payer = user,
+    space = 8 + ClaimReward::INIT_SPACE
+    )]
+    pub reward: Box<Account<'info, ClaimReward>>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Move a `ClaimReward` from the seeds `["reward", task, user]`, with `task`
+/// in decimal, to the namespaced ones of `reward_mint`, keeping its nonce.
+/// The old account was shared by every mint, so the user picks the mint the
+/// task pays in.
+pub fn migrate_reward_seeds(ctx: Context<MigrateRewardSeeds>, _task: u16) -> Result<()> {
+    let legacy = &ctx.accounts.legacy;
+    let reward = &mut ctx.accounts.reward;
+    reward.version = ClaimReward::VERSION;
+    reward.owner = legacy.owner;
+    reward.reward = legacy.reward;
+    reward.times = legacy.times;
+    reward.received = legacy.received;
+    emit!(AccountMoved {
+        from: legacy.key(),
+        to: reward.key(),
+    });
+    Ok(())
+}
+
+/// Create the program-owned PDA `account` with `space` bytes, signing with
+/// `seeds`. Lamports already sent to the address are kept and topped up.
+fn create_pda<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let rent_exempt = Rent::get()?.minimum_balance(space);
+    let lamports = account.lamports();
+    let signer = &[seeds];
+    if lamports == 0 {
+        let cpi_accounts = system_program::CreateAccount {
+            from: payer.to_account_info(),
+            to: account.clone(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(system_program.to_account_info(), cpi_accounts, signer);
+        return system_program::create_account(cpi_ctx, rent_exempt, space as u64, &crate::ID);
+    }
+    if lamports < rent_exempt {
+        let cpi_accounts = Transfer {
+            from: payer.to_account_info(),
+            to: account.clone(),
+        };
+        let cpi_ctx = CpiContext::new(system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, rent_exempt - lamports)?;
+    }
+    let cpi_accounts = system_program::Allocate {
+        account_to_allocate: account.clone(),
+    };
+    let cpi_ctx =
+        CpiContext::new_with_signer(system_program.to_account_info(), cpi_accounts, signer);
+    system_program::allocate(cpi_ctx, space as u64)?;
+    let cpi_accounts = system_program::Assign {
+        account_to_assign: account.clone(),
+    };
+    let cpi_ctx =
+        CpiContext::new_with_signer(system_program.to_account_info(), cpi_accounts, signer);
+    system_program::assign(cpi_ctx, &crate::ID)
+}
+
+/// Account types `migrate` can write, with the layout version they are at.
+trait Versioned {
+    const VERSION: u8;
+}
+
+macro_rules! versioned {
+    ($($account:ty),* $(,)?) => {
+        $(impl Versioned for $account {
+            const VERSION: u8 = <$account>::VERSION;
+        })*
+    };
+}
+
+versioned!(
+    Config,
+    StateAccount,
+    ConsensusState,
+    ClaimReward,
+    Badge,
+    BadgeConfig,
+    BadgeConfigPool,
+);
+
+/// Layout version `data` is in. Accounts written before the version byte
+/// existed are recognised by the size of their `V0` layout; every later
+/// layout starts with its version right after the discriminator.
+fn layout_version<V0: Space>(data: &[u8]) -> Result<u8> {
+    if data.len() == 8 + V0::INIT_SPACE {
+        return Ok(0);
+    }
+    data.get(8)
+        .copied()
+        .ok_or(ErrorCode::UnknownAccountLayout.into())
+}
+
+/// Rewrite `account` as `upgrade(version, data)`, where `version` is the
+/// layout it is in now (see `layout_version`) and `data` its bytes after the
+/// discriminator. Accounts already at `New::VERSION` are refused, as are
+/// versions this program does not know yet. The account is resized to
+/// `New::INIT_SPACE` and `payer` tops up rent when it grows.
+fn migrate<'info, V0, New>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    upgrade: impl FnOnce(u8, &[u8]) -> Result<New>,
+) -> Result<()>
+where
+    V0: Space,
+    New: AccountSerialize + Discriminator + Space + Versioned,
+{
+    let new = {
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == New::DISCRIMINATOR,
+            ErrorCode::UnknownAccountLayout
+        );
+        let version = layout_version::<V0>(&data)?;
+        require!(version != New::VERSION, ErrorCode::AccountAlreadyMigrated);
+        require!(version < New::VERSION, ErrorCode::UnknownAccountLayout);
+        upgrade(version, &data[8..])?
+    };
+
+    let space = 8 + New::INIT_SPACE;
+    let rent_exempt = Rent::get()?.minimum_balance(space);
+    let lamports = account.lamports();
+    if lamports < rent_exempt {
+        let cpi_accounts = Transfer {
+            from: payer.to_account_info(),
+            to: account.clone(),
+        };
+        let cpi_ctx = CpiContext::new(system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, rent_exempt - lamports)?;
+    }
+    account.realloc(space, false)?;
+    new.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+
+    emit!(AccountMigrated {
+        account: account.key(),
+        version: account.try_borrow_data()?[8],
+    });
+    Ok(())
+}
@@ -1,7 +1,15 @@
 pub mod initialize;
+pub mod limits;
+pub mod migrate;
+pub mod owner;
+pub mod pause;
+pub mod signer;
 pub mod update;
 pub mod upload_badge;
 pub mod upload_validation;
 pub mod withdraw;
 
 pub mod claim;
+pub mod close;
+pub mod global;
+pub mod verify;
@@ -0,0 +1,96 @@
+use crate::errors::error::ErrorCode;
+use crate::events::event::*;
+use crate::states::consensus::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ProposeOwner<'info> {
+    #[account(
+    mut,
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
+    )]
+    pub payer: Signer<'info>,
+}
+
+/// First step of an ownership transfer. The current owner keeps full control
+/// until `new_owner` calls `accept_owner`, so a wrong address can simply be
+/// replaced or cancelled.
+pub fn propose_owner(ctx: Context<ProposeOwner>, new_owner: Pubkey) -> Result<()> {
+    let config_state = &mut ctx.accounts.config;
+    if !config_state.initialized {
+        return Err(ErrorCode::NotInitialized.into());
+    }
+    require!(
+        new_owner != Pubkey::default() && new_owner != config_state.owner,
+        ErrorCode::InvalidNewOwner
+    );
+    config_state.pending_owner = new_owner;
+    emit!(OwnershipTransferProposed {
+        owner: config_state.owner,
+        pending_owner: new_owner,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AcceptOwner<'info> {
+    #[account(
+    mut,
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+    constraint = config.pending_owner == new_owner.key() @ ErrorCode::OnlyPendingOwner,
+    )]
+    pub new_owner: Signer<'info>,
+}
+
+pub fn accept_owner(ctx: Context<AcceptOwner>) -> Result<()> {
+    let config_state = &mut ctx.accounts.config;
+    require!(
+        config_state.pending_owner != Pubkey::default(),
+        ErrorCode::NoPendingOwner
+    );
+    let old_owner = config_state.owner;
+    config_state.owner = config_state.pending_owner;
+    config_state.pending_owner = Pubkey::default();
+    emit!(OwnershipTransferred {
+        old_owner,
+        new_owner: config_state.owner,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelOwnerTransfer<'info> {
+    #[account(
+    mut,
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
+    )]
+    pub payer: Signer<'info>,
+}
+
+pub fn cancel_owner_transfer(ctx: Context<CancelOwnerTransfer>) -> Result<()> {
+    let config_state = &mut ctx.accounts.config;
+    require!(
+        config_state.pending_owner != Pubkey::default(),
+        ErrorCode::NoPendingOwner
+    );
+    emit!(OwnershipTransferCancelled {
+        owner: config_state.owner,
+        pending_owner: config_state.pending_owner,
+    });
+    config_state.pending_owner = Pubkey::default();
+    Ok(())
+}
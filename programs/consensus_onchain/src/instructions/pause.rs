@@ -0,0 +1,107 @@
+use crate::errors::error::ErrorCode;
+use crate::events::event::*;
+use crate::states::claim::RewardMint;
+use crate::states::consensus::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetPause<'info> {
+    #[account(
+    mut,
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+    constraint = config.owner == authority.key()
+        || (config.guardian != Pubkey::default() && config.guardian == authority.key())
+        @ ErrorCode::OnlyOwnerOrGuardian,
+    )]
+    pub authority: Signer<'info>,
+}
+
+/// Replace the pause bitmap. The guardian may only add flags.
+pub fn set_pause(ctx: Context<SetPause>, paused: u8) -> Result<()> {
+    let config_state = &mut ctx.accounts.config;
+    if !config_state.initialized {
+        return Err(ErrorCode::NotInitialized.into());
+    }
+    require!(paused & !PAUSE_ALL == 0, ErrorCode::InvalidPauseFlags);
+    if config_state.owner != ctx.accounts.authority.key() {
+        require!(
+            paused & config_state.paused == config_state.paused,
+            ErrorCode::GuardianCannotUnpause
+        );
+    }
+    config_state.paused = paused;
+    emit!(PauseSet {
+        by: ctx.accounts.authority.key(),
+        paused,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    #[account(
+    mut,
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
+    )]
+    pub payer: Signer<'info>,
+}
+
+/// Set the guardian, or remove it with `Pubkey::default()`.
+pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+    let config_state = &mut ctx.accounts.config;
+    if !config_state.initialized {
+        return Err(ErrorCode::NotInitialized.into());
+    }
+    config_state.guardian = guardian;
+    emit!(GuardianSet { guardian });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetMintPaused<'info> {
+    #[account(
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        mut,
+        seeds = [b"reward_mint".as_ref(), reward_mint.mint.as_ref()],
+        bump = reward_mint.bump,
+    )]
+    pub reward_mint: Box<Account<'info, RewardMint>>,
+    #[account(
+    constraint = config.owner == authority.key()
+        || (config.guardian != Pubkey::default() && config.guardian == authority.key())
+        @ ErrorCode::OnlyOwnerOrGuardian,
+    )]
+    pub authority: Signer<'info>,
+}
+
+/// Pause or resume claims in one reward mint. The guardian may only pause.
+pub fn set_mint_paused(ctx: Context<SetMintPaused>, paused: bool) -> Result<()> {
+    let config_state = &ctx.accounts.config;
+    if !config_state.initialized {
+        return Err(ErrorCode::NotInitialized.into());
+    }
+    if config_state.owner != ctx.accounts.authority.key() {
+        require!(paused, ErrorCode::GuardianCannotUnpause);
+    }
+    let reward_mint = &mut ctx.accounts.reward_mint;
+    reward_mint.paused = paused;
+    emit!(MintPauseSet {
+        by: ctx.accounts.authority.key(),
+        mint: reward_mint.mint,
+        paused,
+    });
+    Ok(())
+}
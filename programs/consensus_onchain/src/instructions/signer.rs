@@ -0,0 +1,161 @@
+use crate::errors::error::ErrorCode;
+use crate::events::event::*;
+use crate::states::consensus::*;
+use crate::states::signer::*;
+use crate::utils;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetSigner<'info> {
+    #[account(
+    mut,
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+    init_if_needed,
+    seeds = [b"signers".as_ref()],
+    bump,
+    payer = payer,
+    space = 8 + SignerRegistry::INIT_SPACE
+    )]
+    pub signer_registry: Box<Account<'info, SignerRegistry>>,
+    #[account(mut,
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
+    )]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Add a key to the registry, or replace the settings of a registered key.
+/// The first call switches authorization over to the registry, so the owner
+/// registers `Config.signer` too if it should stay trusted.
+pub fn set_signer(
+    ctx: Context<SetSigner>,
+    key: TrustedSigner,
+    enabled: bool,
+    valid_from: u64,
+    valid_until: u64,
+    scope: u8,
+) -> Result<()> {
+    if !ctx.accounts.config.initialized {
+        return Err(ErrorCode::NotInitialized.into());
+    }
+    require!(
+        scope != 0 && scope & !SCOPE_ALL == 0,
+        ErrorCode::InvalidSignerScope
+    );
+    require!(
+        valid_until == 0 || valid_until >= valid_from,
+        ErrorCode::InvalidSlotRange
+    );
+
+    ctx.accounts.config.signer_registry_active = true;
+    let registry = &mut ctx.accounts.signer_registry;
+    registry.version = SignerRegistry::VERSION;
+    registry.bump = ctx.bumps.signer_registry;
+    let entry = SignerEntry {
+        key,
+        enabled,
+        valid_from,
+        valid_until,
+        scope,
+    };
+    match registry.position(&key) {
+        Some(i) => registry.signers[i] = entry,
+        None => {
+            require!(
+                registry.signers.len() < MAX_SIGNERS,
+                ErrorCode::SignerRegistryFull
+            );
+            registry.signers.push(entry);
+        }
+    }
+    utils::check_cosign_policy(&ctx.accounts.config, Some(registry))?;
+    emit!(SignerSet {
+        key,
+        enabled,
+        valid_from,
+        valid_until,
+        scope,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RemoveSigner<'info> {
+    #[account(
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+    mut,
+    seeds = [b"signers".as_ref()],
+    bump = signer_registry.bump,
+    )]
+    pub signer_registry: Box<Account<'info, SignerRegistry>>,
+    #[account(
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
+    )]
+    pub payer: Signer<'info>,
+}
+
+pub fn remove_signer(ctx: Context<RemoveSigner>, key: TrustedSigner) -> Result<()> {
+    let registry = &mut ctx.accounts.signer_registry;
+    let i = registry.position(&key).ok_or(ErrorCode::SignerNotFound)?;
+    registry.signers.remove(i);
+    utils::check_cosign_policy(&ctx.accounts.config, Some(registry))?;
+    emit!(SignerRemoved { key });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetCosignPolicy<'info> {
+    #[account(
+    mut,
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    /// Required once the registry is in use.
+    #[account(
+    seeds = [b"signers".as_ref()],
+    bump = signer_registry.bump,
+    )]
+    pub signer_registry: Option<Box<Account<'info, SignerRegistry>>>,
+    #[account(
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
+    )]
+    pub payer: Signer<'info>,
+}
+
+/// Require `required` distinct signers on claims paying more than `threshold`.
+/// Global publications always need `required` signers. `required` may not
+/// exceed the number of keys able to sign claims or publications.
+pub fn set_cosign_policy(
+    ctx: Context<SetCosignPolicy>,
+    threshold: u64,
+    required: u8,
+) -> Result<()> {
+    let config_state = &mut ctx.accounts.config;
+    if !config_state.initialized {
+        return Err(ErrorCode::NotInitialized.into());
+    }
+    require!(required >= 1, ErrorCode::InvalidCosignPolicy);
+    config_state.cosign_threshold = threshold;
+    config_state.cosign_required = required;
+    utils::check_cosign_policy(
+        config_state,
+        ctx.accounts
+            .signer_registry
+            .as_deref()
+            .map(|registry| &**registry),
+    )?;
+    emit!(CosignPolicySet {
+        threshold,
+        required
+    });
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use crate::errors::error::ErrorCode;
+use crate::events::event::*;
 use crate::states::consensus::*;
+use crate::states::signer::*;
 use anchor_lang::prelude::*;
 #[derive(Accounts)]
 pub struct Update<'info> {
@@ -9,6 +11,14 @@ pub struct Update<'info> {
     bump,
     )]
     pub config: Box<Account<'info, Config>>,
+    #[account(
+    init_if_needed,
+    seeds = [b"signer_history".as_ref()],
+    bump,
+    payer = payer,
+    space = 8 + SignerHistory::INIT_SPACE
+    )]
+    pub signer_history: Box<Account<'info, SignerHistory>>,
     #[account(mut,
     constraint = config.owner == payer.key()
     )]
@@ -16,15 +26,53 @@ pub struct Update<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn update(ctx: Context<Update>, signer: Pubkey, fee: u64) -> Result<()> {
+pub fn update(
+    ctx: Context<Update>,
+    signer: TrustedSigner,
+    fee: u64,
+    signer_grace_slots: u64,
+    max_validity: i64,
+) -> Result<()> {
     let config_state = &mut ctx.accounts.config;
     if !config_state.initialized {
         return Err(ErrorCode::NotInitialized.into());
     }
+    require!(max_validity >= 0, ErrorCode::InvalidMaxValidity);
+    let history = &mut ctx.accounts.signer_history;
+    history.version = SignerHistory::VERSION;
+    history.bump = ctx.bumps.signer_history;
     // if config_state.owner != *ctx.accounts.payer.key{
     //     return Err(ErrorCode::AccountError.into());
     // }
-    config_state.signer = signer;
+    if config_state.signer != signer {
+        let slot = Clock::get()?.slot;
+        // Once the registry is in use `Config.signer` is no longer trusted on
+        // its own, so retiring it must not open a grace window.
+        if !config_state.signer_registry_active {
+            history.push(RetiredSigner {
+                key: config_state.signer,
+                valid_from: config_state.signer_since,
+                retired_at: slot,
+                grace_until: slot.saturating_add(signer_grace_slots),
+                scope: SCOPE_ALL,
+            });
+        }
+        emit!(SignerRotated {
+            old_signer: config_state.signer,
+            new_signer: signer,
+            effective_slot: slot,
+        });
+        config_state.signer = signer;
+        config_state.signer_since = slot;
+    }
     config_state.fee = fee;
+    config_state.signer_grace_slots = signer_grace_slots;
+    config_state.max_validity = max_validity;
+    emit!(ConfigUpdated {
+        signer,
+        fee,
+        signer_grace_slots,
+        max_validity,
+    });
     Ok(())
 }
@@ -1,24 +1,14 @@
 use crate::errors::error::ErrorCode;
+use crate::events::event::*;
+use crate::payloads::{self, BadgeInfo, KIND_BADGE};
 use crate::states::badge::*;
-use crate::states::consensus::Config;
+use crate::states::consensus::*;
+use crate::states::profile::*;
+use crate::states::signer::*;
 use crate::utils;
 use anchor_lang::prelude::*;
-use serde::{Deserialize, Serialize};
-use solana_program::instruction::Instruction;
-use solana_program::system_instruction;
-use solana_program::sysvar::instructions::{
-    load_current_index_checked, load_instruction_at_checked, ID as IX_ID,
-};
-
-#[derive(Serialize, Deserialize)]
-struct BadgeInfo {
-    /// Quiz id.
-    pub quiz: u64,
-    /// Tier of badge.
-    pub tier: u64,
-    /// Owner of badge.
-    pub owner: Pubkey,
-}
+use solana_program::sysvar::instructions::ID as IX_ID;
+
 #[derive(Accounts)]
 #[instruction(quiz:u64)]
 pub struct UploadBadge<'info> {
@@ -52,12 +42,38 @@ pub struct UploadBadge<'info> {
 
     #[account(
     init,
-    seeds = [format!("{}", quiz).as_ref(), user.key().as_ref()],
+    seeds = [b"badge".as_ref(), quiz.to_le_bytes().as_ref(), user.key().as_ref()],
     bump,
     payer = user,
     space = 8 + Badge::INIT_SPACE
     )]
     pub badge: Box<Account<'info, Badge>>,
+    /// CHECK: Address of the same badge under the old seeds, which must be
+    /// moved with `migrate_badge_seeds` first.
+    #[account(
+    seeds = [format!("{}", quiz).as_ref(), user.key().as_ref()],
+    bump,
+    constraint = !utils::holds_account::<Badge>(&legacy_badge) @ ErrorCode::LegacySeedsNotMigrated,
+    )]
+    pub legacy_badge: UncheckedAccount<'info>,
+    #[account(
+    init_if_needed,
+    seeds = [b"profile".as_ref(), user.key().as_ref()],
+    bump,
+    payer = user,
+    space = 8 + ContributorProfile::INIT_SPACE
+    )]
+    pub profile: Box<Account<'info, ContributorProfile>>,
+    #[account(
+    seeds = [b"signers".as_ref()],
+    bump = signer_registry.bump,
+    )]
+    pub signer_registry: Option<Box<Account<'info, SignerRegistry>>>,
+    #[account(
+    seeds = [b"signer_history".as_ref()],
+    bump = signer_history.bump,
+    )]
+    pub signer_history: Option<Box<Account<'info, SignerHistory>>>,
     /// CHECK: The address check is needed because otherwise
     /// the supplied Sysvar could be anything else.
     /// The Instruction Sysvar has not been implemented
@@ -72,13 +88,10 @@ pub fn upload_badge(
     msg: Vec<u8>,
     sig: [u8; 64],
 ) -> Result<()> {
-    msg!("quiz:{}, msg:{:?}", quiz, msg);
     require!(quiz != 0, ErrorCode::InvalidQuizIDError);
 
-    let msg_str = String::from_utf8(msg.clone()).map_err(|_| ErrorCode::MessageShouldString)?;
-
-    let badge_info: BadgeInfo =
-        serde_json::from_str(&msg_str).map_err(|_| ErrorCode::MessageShouldJson)?;
+    let badge_info: BadgeInfo = payloads::decode(&msg)?;
+    require!(quiz == badge_info.quiz, ErrorCode::QuizMismatch);
 
     let owner = *ctx.accounts.user.key;
     require!(owner == badge_info.owner, ErrorCode::InvalidOwnerError);
@@ -88,18 +101,33 @@ pub fn upload_badge(
     if !config_state.initialized {
         return Err(ErrorCode::NotInitialized.into());
     }
-
-    let signer_key = config_state.signer.to_bytes();
-
-    // Get what should be the Ed25519Program instruction
-    let index = load_current_index_checked(&ctx.accounts.ix_sysvar)?;
-    let ix: Instruction = load_instruction_at_checked((index - 1).into(), &ctx.accounts.ix_sysvar)?;
-
-    // Check that ix is what we expect to have been sent
-    utils::verify_ed25519_ix(&ix, &signer_key, &msg, &sig)?;
+    config_state.check_not_paused(PAUSE_BADGE)?;
+
+    badge_info
+        .domain
+        .check(KIND_BADGE, config_state.deployment)?;
+    payloads::check_expiry(badge_info.expires_at, config_state.max_validity)?;
+
+    let signatures = utils::load_signatures(&ctx.accounts.ix_sysvar)?;
+    let signer = utils::verify_signer(
+        &signatures,
+        config_state,
+        ctx.accounts
+            .signer_registry
+            .as_deref()
+            .map(|registry| &**registry),
+        ctx.accounts
+            .signer_history
+            .as_deref()
+            .map(|history| &**history),
+        SCOPE_BADGE,
+        &msg,
+        &sig,
+    )?;
 
     let badge = &mut ctx.accounts.badge;
 
+    badge.version = Badge::VERSION;
     badge.owner = owner;
 
     badge.quiz = quiz;
@@ -108,7 +136,10 @@ pub fn upload_badge(
 
     let badge_config_pool = &mut ctx.accounts.badge_config_pool;
 
+    badge_config_pool.version = BadgeConfigPool::VERSION;
+
     let badge_config = &mut ctx.accounts.badge_config;
+    badge_config.version = BadgeConfig::VERSION;
     if badge_config.quiz == 0 {
         badge_config.quiz = quiz;
         badge_config_pool.config_count += 1;
@@ -116,5 +147,18 @@ pub fn upload_badge(
     badge_config.total += 1;
     badge_config_pool.total += 1;
 
+    let profile = &mut ctx.accounts.profile;
+    profile.version = ContributorProfile::VERSION;
+    profile.user = ctx.accounts.user.key();
+    profile.bump = ctx.bumps.profile;
+    profile.badges += 1;
+
+    emit!(BadgeIssued {
+        owner,
+        quiz,
+        tier: badge_info.tier,
+        signer,
+    });
+
     Ok(())
 }
@@ -1,19 +1,14 @@
 use crate::errors::error::ErrorCode;
+use crate::events::event::*;
+use crate::payloads::{self, Validation, KIND_UPLOAD};
 use crate::states::consensus::*;
+use crate::states::profile::*;
+use crate::states::signer::*;
 use crate::utils;
 use anchor_lang::prelude::*;
-use serde::{Deserialize, Serialize};
-use solana_program::instruction::Instruction;
 use solana_program::system_instruction;
-use solana_program::sysvar::instructions::{
-    load_current_index_checked, load_instruction_at_checked, ID as IX_ID,
-};
+use solana_program::sysvar::instructions::ID as IX_ID;
 
-#[derive(Serialize, Deserialize)]
-struct Validation {
-    timestamp: u64,
-    consensus_proof: String,
-}
 #[derive(Accounts)]
 #[instruction(timestamp:u64)]
 pub struct UploadValidation<'info> {
@@ -25,15 +20,45 @@ pub struct UploadValidation<'info> {
     bump,
     )]
     pub config: Box<Account<'info, Config>>,
+    #[account(
+    init_if_needed,
+    seeds = [b"upload_window".as_ref()],
+    bump,
+    payer = user,
+    space = 8 + UploadWindow::INIT_SPACE
+    )]
+    pub upload_window: Box<Account<'info, UploadWindow>>,
 
     #[account(
     init,
-    seeds = [format!("{}", timestamp).as_ref(), user.key().as_ref()],
+    seeds = [
+        b"consensus".as_ref(),
+        upload_window.epoch(timestamp).to_le_bytes().as_ref(),
+        user.key().as_ref(),
+    ],
     bump,
     payer = user,
     space = 8 + ConsensusState::INIT_SPACE
     )]
     pub consensus: Box<Account<'info, ConsensusState>>,
+    #[account(
+    init_if_needed,
+    seeds = [b"profile".as_ref(), user.key().as_ref()],
+    bump,
+    payer = user,
+    space = 8 + ContributorProfile::INIT_SPACE
+    )]
+    pub profile: Box<Account<'info, ContributorProfile>>,
+    #[account(
+    seeds = [b"signers".as_ref()],
+    bump = signer_registry.bump,
+    )]
+    pub signer_registry: Option<Box<Account<'info, SignerRegistry>>>,
+    #[account(
+    seeds = [b"signer_history".as_ref()],
+    bump = signer_history.bump,
+    )]
+    pub signer_history: Option<Box<Account<'info, SignerHistory>>>,
     /// CHECK: The address check is needed because otherwise
     /// the supplied Sysvar could be anything else.
     /// The Instruction Sysvar has not been implemented
@@ -48,54 +73,61 @@ pub fn upload_validation(
     msg: Vec<u8>,
     sig: [u8; 64],
 ) -> Result<()> {
-    let msg_str = String::from_utf8(msg.clone()).map_err(|_| ErrorCode::MessageShouldString)?;
-
-    let validation: Validation =
-        serde_json::from_str(&msg_str).map_err(|_| ErrorCode::MessageShouldJson)?;
+    let validation: Validation = payloads::decode(&msg)?;
+    // `timestamp` seeds the consensus PDA, so it must be the signed one.
+    require!(
+        timestamp == validation.timestamp,
+        ErrorCode::TimestampMismatch
+    );
 
     let config_state = &ctx.accounts.config;
 
     if !config_state.initialized {
         return Err(ErrorCode::NotInitialized.into());
     }
-
-    let signer_key = config_state.signer.to_bytes();
-
-    let user_signer = if config_state.signer == *ctx.accounts.user.key {
-        true
-    } else {
-        false
-    };
-
-    // Get what should be the Ed25519Program instruction
-    let index = load_current_index_checked(&ctx.accounts.ix_sysvar)?;
-    let ix: Instruction = load_instruction_at_checked((index - 1).into(), &ctx.accounts.ix_sysvar)?;
-
-    // Check that ix is what we expect to have been sent
-    utils::verify_ed25519_ix(&ix, &signer_key, &msg, &sig)?;
+    config_state.check_not_paused(PAUSE_UPLOAD)?;
+
+    validation
+        .domain
+        .check(KIND_UPLOAD, config_state.deployment)?;
+    payloads::check_expiry(validation.expires_at, config_state.max_validity)?;
+
+    let upload_window = &mut ctx.accounts.upload_window;
+    upload_window.init_defaults(ctx.bumps.upload_window);
+    upload_window.check(validation.timestamp, Clock::get()?.unix_timestamp)?;
+    let epoch = upload_window.epoch(validation.timestamp);
+    let epoch_start = epoch * upload_window.epoch_length;
+
+    let signatures = utils::load_signatures(&ctx.accounts.ix_sysvar)?;
+    let signer = utils::verify_signer(
+        &signatures,
+        config_state,
+        ctx.accounts
+            .signer_registry
+            .as_deref()
+            .map(|registry| &**registry),
+        ctx.accounts
+            .signer_history
+            .as_deref()
+            .map(|history| &**history),
+        SCOPE_UPLOAD,
+        &msg,
+        &sig,
+    )?;
 
     let consensus_state = &mut ctx.accounts.consensus;
 
-    consensus_state.timestamp = validation.timestamp;
-
-    consensus_state.global = user_signer;
-
-    let mut proof = &validation.consensus_proof[..];
-
-    if validation.consensus_proof.to_lowercase().starts_with("0x") {
-        proof = &validation.consensus_proof[2..];
-    }
-    let hex_data = hex::decode(proof).unwrap();
+    consensus_state.version = ConsensusState::VERSION;
+    consensus_state.bump = ctx.bumps.consensus;
+    consensus_state.timestamp = epoch_start;
 
-    if hex_data.len() != 32 {
-        return Err(ErrorCode::ProofLengthError.into());
-    }
-    let array_bytes: [u8; 32] = hex_data
-        .as_slice()
-        .try_into()
-        .map_err(|_| ErrorCode::ProofDataError)?;
+    consensus_state.consensus_proof = validation.consensus_proof;
 
-    consensus_state.consensus_proof = array_bytes;
+    let profile = &mut ctx.accounts.profile;
+    profile.version = ContributorProfile::VERSION;
+    profile.user = ctx.accounts.user.key();
+    profile.bump = ctx.bumps.profile;
+    profile.record_upload(epoch_start, upload_window.epoch_length);
 
     let upload_fee = config_state.fee;
     // Charge an upload fee, if one exists
@@ -112,6 +144,69 @@ pub fn upload_validation(
                 ctx.accounts.config.to_account_info(),
             ],
         )?;
+        let config_state = &mut ctx.accounts.config;
+        config_state.fees_collected = config_state.fees_collected.saturating_add(upload_fee);
+    }
+
+    emit!(ConsensusUploaded {
+        user: ctx.accounts.user.key(),
+        epoch,
+        timestamp: epoch_start,
+        consensus_proof: validation.consensus_proof,
+        fee: upload_fee,
+        signer,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetUploadWindow<'info> {
+    #[account(
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+    init_if_needed,
+    seeds = [b"upload_window".as_ref()],
+    bump,
+    payer = payer,
+    space = 8 + UploadWindow::INIT_SPACE
+    )]
+    pub upload_window: Box<Account<'info, UploadWindow>>,
+    #[account(mut,
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
+    )]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Set the upload epoch length and how far from the cluster clock an
+/// upload's timestamp may lie. A new epoch length only applies to later
+/// uploads; existing accounts keep the epoch they were created under.
+pub fn set_upload_window(
+    ctx: Context<SetUploadWindow>,
+    epoch_length: u64,
+    max_past: i64,
+    max_future: i64,
+) -> Result<()> {
+    if !ctx.accounts.config.initialized {
+        return Err(ErrorCode::NotInitialized.into());
     }
+    require!(
+        epoch_length > 0 && max_past >= 0 && max_future >= 0,
+        ErrorCode::InvalidUploadWindow
+    );
+    let upload_window = &mut ctx.accounts.upload_window;
+    upload_window.version = UploadWindow::VERSION;
+    upload_window.epoch_length = epoch_length;
+    upload_window.max_past = max_past;
+    upload_window.max_future = max_future;
+    upload_window.bump = ctx.bumps.upload_window;
+    emit!(UploadWindowSet {
+        epoch_length,
+        max_past,
+        max_future,
+    });
     Ok(())
 }
@@ -0,0 +1,41 @@
+use crate::errors::error::ErrorCode;
+use crate::states::consensus::*;
+use crate::states::global::*;
+use crate::utils::{self, MerkleHash, MAX_PROOF_DEPTH};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct VerifyInclusion<'info> {
+    /// CHECK: A `ConsensusState` or `GlobalConsensus`, told apart by its
+    /// discriminator in `published_root`.
+    #[account(owner = crate::ID)]
+    pub root: UncheckedAccount<'info>,
+}
+
+/// Check that `leaf` is in the Merkle tree whose root `root` holds: the
+/// `consensus_proof` of a `ConsensusState` or the `root` of a
+/// `GlobalConsensus`. The result is returned, not raised, so programs can
+/// call this through CPI and read it from the return data.
+pub fn verify_inclusion(
+    ctx: Context<VerifyInclusion>,
+    leaf: [u8; 32],
+    path: Vec<[u8; 32]>,
+    hash: MerkleHash,
+) -> Result<bool> {
+    // A leaf alone is never a root: single-leaf trees are not supported.
+    require!(!path.is_empty(), ErrorCode::EmptyProof);
+    require!(path.len() <= MAX_PROOF_DEPTH, ErrorCode::ProofTooLong);
+    let root = published_root(&ctx.accounts.root)?;
+    Ok(utils::merkle_root(leaf, &path, hash) == root)
+}
+
+fn published_root(info: &AccountInfo) -> Result<[u8; 32]> {
+    let data = info.try_borrow_data()?;
+    if let Ok(state) = ConsensusState::try_deserialize(&mut &data[..]) {
+        return Ok(state.consensus_proof);
+    }
+    if let Ok(global) = GlobalConsensus::try_deserialize(&mut &data[..]) {
+        return Ok(global.root);
+    }
+    Err(ErrorCode::InvalidRootAccount.into())
+}
@@ -1,7 +1,9 @@
 use crate::errors::error::ErrorCode;
+use crate::events::event::*;
+use crate::states::claim::*;
 use crate::states::consensus::*;
 use anchor_lang::prelude::*;
-use solana_program::system_instruction;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     #[account(
@@ -10,6 +12,11 @@ pub struct Withdraw<'info> {
     bump,
     )]
     pub config: Box<Account<'info, Config>>,
+    /// CHECK: Only receives lamports, any account but `config` will do.
+    #[account(mut,
+    constraint = destination.key() != config.key() @ ErrorCode::InvalidWithdrawDestination,
+    )]
+    pub destination: UncheckedAccount<'info>,
     #[account(mut,
     constraint = config.owner == payer.key()
     )]
@@ -17,31 +24,103 @@ pub struct Withdraw<'info> {
     pub system_program: Program<'info, System>,
 }
 
+/// Move collected fees from `Config` to `destination`. `amount == 0`
+/// withdraws everything above the rent-exempt minimum.
 pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+    let config_info = ctx.accounts.config.to_account_info();
     let config_state = &mut ctx.accounts.config;
     if !config_state.initialized {
         return Err(ErrorCode::NotInitialized.into());
     }
-    let mut balance = **ctx
-        .accounts
-        .config
-        .to_account_info()
-        .try_borrow_lamports()?;
-    if amount != 0 {
-        balance = amount;
-    }
+    config_state.check_not_paused(PAUSE_WITHDRAW)?;
 
-    // ctx.accounts.config.sub_lamports(balance)?;
-    // ctx.accounts.payer.add_lamports(balance)?;
-    **ctx
-        .accounts
-        .config
-        .to_account_info()
-        .try_borrow_mut_lamports()? -= balance;
+    let rent_exempt = Rent::get()?.minimum_balance(config_info.data_len());
+    let available = config_info.lamports().saturating_sub(rent_exempt);
+    let amount = if amount == 0 { available } else { amount };
+    require!(
+        amount <= available,
+        ErrorCode::InsufficientWithdrawableBalance
+    );
+
+    **config_info.try_borrow_mut_lamports()? -= amount;
     **ctx
         .accounts
-        .payer
+        .destination
         .to_account_info()
-        .try_borrow_mut_lamports()? += balance;
+        .try_borrow_mut_lamports()? += amount;
+    config_state.total_withdrawn = config_state.total_withdrawn.saturating_add(amount);
+    emit!(FeesWithdrawn {
+        to: ctx.accounts.destination.key(),
+        amount,
+        total_withdrawn: config_state.total_withdrawn,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTokens<'info> {
+    #[account(
+    seeds = [b"config".as_ref()],
+    bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        seeds = [b"state".as_ref()],
+        bump = state.bump,
+    )]
+    pub state: Box<Account<'info, StateAccount>>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"reward_mint".as_ref(), mint.key().as_ref()],
+        bump = reward_mint.bump,
+    )]
+    pub reward_mint: Box<Account<'info, RewardMint>>,
+    #[account(
+        mut,
+        address = reward_mint.vault @ ErrorCode::InvalidTokenAccount,
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = destination.mint == mint.key() @ ErrorCode::InvalidTokenAccount,
+    )]
+    pub destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+    constraint = config.owner == payer.key() @ ErrorCode::OnlyOwner,
+    )]
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Move `amount` tokens out of the vault of a registered reward mint, enabled
+/// or not.
+pub fn withdraw_tokens(ctx: Context<WithdrawTokens>, amount: u64) -> Result<()> {
+    let config_state = &ctx.accounts.config;
+    if !config_state.initialized {
+        return Err(ErrorCode::NotInitialized.into());
+    }
+    config_state.check_not_paused(PAUSE_WITHDRAW)?;
+
+    let reward_mint = &mut ctx.accounts.reward_mint;
+    reward_mint.withdrawn = reward_mint.withdrawn.saturating_add(amount);
+    let seeds = &[b"state".as_ref(), &[reward_mint.authority_bump]];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = TransferChecked {
+        from: ctx.accounts.token_vault.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.destination.to_account_info(),
+        authority: ctx.accounts.state.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    emit!(TokensWithdrawn {
+        mint: ctx.accounts.mint.key(),
+        vault: ctx.accounts.token_vault.key(),
+        to: ctx.accounts.destination.key(),
+        amount,
+    });
     Ok(())
 }
@@ -1,14 +1,26 @@
 use anchor_lang::prelude::*;
 mod errors;
+mod events;
 mod instructions;
+pub mod payloads;
 mod states;
 mod utils;
 use instructions::claim::*;
+use instructions::close::*;
+use instructions::global::*;
 use instructions::initialize::*;
+use instructions::limits::*;
+use instructions::migrate::*;
+use instructions::owner::*;
+use instructions::pause::*;
+use instructions::signer::*;
 use instructions::update::*;
 use instructions::upload_badge::*;
 use instructions::upload_validation::*;
+use instructions::verify::*;
 use instructions::withdraw::*;
+use states::signer::TrustedSigner;
+use utils::MerkleHash;
 
 declare_id!("B2fHGq6iwRPGmn3KBUFBgQpxVnDGFQT3ZjD2vJTDphZn");
 
@@ -16,8 +28,13 @@ declare_id!("B2fHGq6iwRPGmn3KBUFBgQpxVnDGFQT3ZjD2vJTDphZn");
 pub mod consensus_onchain {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>, signer: Pubkey, fee: u64) -> Result<()> {
-        instructions::initialize::initialize(ctx, signer, fee)
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        signer: TrustedSigner,
+        fee: u64,
+        deployment: u64,
+    ) -> Result<()> {
+        instructions::initialize::initialize(ctx, signer, fee, deployment)
     }
     pub fn upload_validation(
         ctx: Context<UploadValidation>,
@@ -28,14 +45,120 @@ pub mod consensus_onchain {
         instructions::upload_validation::upload_validation(ctx, timestamp, msg, sig)
     }
 
-    pub fn update(ctx: Context<Update>, signer: Pubkey, fee: u64) -> Result<()> {
-        instructions::update::update(ctx, signer, fee)
+    pub fn publish_global_consensus(
+        ctx: Context<PublishGlobalConsensus>,
+        epoch_start: u64,
+        msg: Vec<u8>,
+        sig: [u8; 64],
+    ) -> Result<()> {
+        instructions::global::publish_global_consensus(ctx, epoch_start, msg, sig)
+    }
+
+    pub fn verify_inclusion(
+        ctx: Context<VerifyInclusion>,
+        leaf: [u8; 32],
+        path: Vec<[u8; 32]>,
+        hash: MerkleHash,
+    ) -> Result<bool> {
+        instructions::verify::verify_inclusion(ctx, leaf, path, hash)
+    }
+
+    pub fn set_upload_window(
+        ctx: Context<SetUploadWindow>,
+        epoch_length: u64,
+        max_past: i64,
+        max_future: i64,
+    ) -> Result<()> {
+        instructions::upload_validation::set_upload_window(ctx, epoch_length, max_past, max_future)
+    }
+
+    pub fn set_consensus_retention(
+        ctx: Context<SetConsensusRetention>,
+        retention: i64,
+    ) -> Result<()> {
+        instructions::close::set_consensus_retention(ctx, retention)
+    }
+
+    pub fn close_consensus(ctx: Context<CloseConsensus>, epoch: u64) -> Result<()> {
+        instructions::close::close_consensus(ctx, epoch)
+    }
+
+    pub fn close_legacy_consensus(ctx: Context<CloseLegacyConsensus>, key: u64) -> Result<()> {
+        instructions::close::close_legacy_consensus(ctx, key)
+    }
+
+    pub fn close_consensus_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseConsensusBatch<'info>>,
+        epochs: Vec<u64>,
+    ) -> Result<()> {
+        instructions::close::close_consensus_batch(ctx, epochs)
+    }
+
+    pub fn update(
+        ctx: Context<Update>,
+        signer: TrustedSigner,
+        fee: u64,
+        signer_grace_slots: u64,
+        max_validity: i64,
+    ) -> Result<()> {
+        instructions::update::update(ctx, signer, fee, signer_grace_slots, max_validity)
     }
 
     pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
         instructions::withdraw::withdraw(ctx, amount)
     }
 
+    pub fn withdraw_tokens(ctx: Context<WithdrawTokens>, amount: u64) -> Result<()> {
+        instructions::withdraw::withdraw_tokens(ctx, amount)
+    }
+
+    pub fn propose_owner(ctx: Context<ProposeOwner>, new_owner: Pubkey) -> Result<()> {
+        instructions::owner::propose_owner(ctx, new_owner)
+    }
+
+    pub fn accept_owner(ctx: Context<AcceptOwner>) -> Result<()> {
+        instructions::owner::accept_owner(ctx)
+    }
+
+    pub fn cancel_owner_transfer(ctx: Context<CancelOwnerTransfer>) -> Result<()> {
+        instructions::owner::cancel_owner_transfer(ctx)
+    }
+
+    pub fn set_pause(ctx: Context<SetPause>, paused: u8) -> Result<()> {
+        instructions::pause::set_pause(ctx, paused)
+    }
+
+    pub fn set_mint_paused(ctx: Context<SetMintPaused>, paused: bool) -> Result<()> {
+        instructions::pause::set_mint_paused(ctx, paused)
+    }
+
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        instructions::pause::set_guardian(ctx, guardian)
+    }
+
+    pub fn set_signer(
+        ctx: Context<SetSigner>,
+        key: TrustedSigner,
+        enabled: bool,
+        valid_from: u64,
+        valid_until: u64,
+        scope: u8,
+    ) -> Result<()> {
+        instructions::signer::set_signer(ctx, key, enabled, valid_from, valid_until, scope)
+    }
+
+    pub fn remove_signer(ctx: Context<RemoveSigner>, key: TrustedSigner) -> Result<()> {
+        instructions::signer::remove_signer(ctx, key)
+    }
+
+    pub fn set_cosign_policy(
+        ctx: Context<SetCosignPolicy>,
+        threshold: u64,
+        required: u8,
+    ) -> Result<()> {
+        instructions::signer::set_cosign_policy(ctx, threshold, required)
+    }
+
     pub fn upload_badge(
         ctx: Context<UploadBadge>,
         quiz: u64,
@@ -45,24 +168,68 @@ pub mod consensus_onchain {
         instructions::upload_badge::upload_badge(ctx, quiz, msg, sig)
     }
 
-    pub fn ini_claim(ctx: Context<IniClaim>) -> Result<()> {
-        instructions::claim::ini_claim(ctx)
+    pub fn register_reward_mint(
+        ctx: Context<RegisterRewardMint>,
+        enabled: bool,
+        force: bool,
+    ) -> Result<()> {
+        instructions::claim::register_reward_mint(ctx, enabled, force)
     }
 
     pub fn claim(ctx: Context<Claim>, task: u16, msg: Vec<u8>, sig: [u8; 64]) -> Result<()> {
         instructions::claim::claim(ctx, task, msg, sig)
     }
 
-    pub fn ini_claim_public(ctx: Context<IniClaimPublic>) -> Result<()> {
-        instructions::claim::ini_claim_public(ctx)
+    pub fn set_transfer_fee_mode(ctx: Context<SetTransferFeeMode>, gross_up: bool) -> Result<()> {
+        instructions::claim::set_transfer_fee_mode(ctx, gross_up)
     }
 
-    pub fn claim_public(
-        ctx: Context<ClaimPublic>,
-        task: u16,
-        msg: Vec<u8>,
-        sig: [u8; 64],
+    pub fn set_mint_limits(
+        ctx: Context<SetMintLimits>,
+        max_per_claim: u64,
+        user_window_cap: u64,
+        epoch_cap: u64,
     ) -> Result<()> {
-        instructions::claim::claim_public(ctx, task, msg, sig)
+        instructions::limits::set_mint_limits(ctx, max_per_claim, user_window_cap, epoch_cap)
+    }
+
+    pub fn migrate_config(ctx: Context<MigrateConfig>, deployment: u64) -> Result<()> {
+        instructions::migrate::migrate_config(ctx, deployment)
+    }
+
+    pub fn migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+        instructions::migrate::migrate_state(ctx)
+    }
+
+    pub fn migrate_consensus(ctx: Context<Migrate>) -> Result<()> {
+        instructions::migrate::migrate_consensus(ctx)
+    }
+
+    pub fn migrate_claim_reward(ctx: Context<Migrate>) -> Result<()> {
+        instructions::migrate::migrate_claim_reward(ctx)
+    }
+
+    pub fn migrate_badge(ctx: Context<Migrate>) -> Result<()> {
+        instructions::migrate::migrate_badge(ctx)
+    }
+
+    pub fn migrate_badge_config(ctx: Context<Migrate>) -> Result<()> {
+        instructions::migrate::migrate_badge_config(ctx)
+    }
+
+    pub fn migrate_badge_config_pool(ctx: Context<Migrate>) -> Result<()> {
+        instructions::migrate::migrate_badge_config_pool(ctx)
+    }
+
+    pub fn migrate_consensus_seeds(ctx: Context<MigrateConsensusSeeds>, key: u64) -> Result<()> {
+        instructions::migrate::migrate_consensus_seeds(ctx, key)
+    }
+
+    pub fn migrate_badge_seeds(ctx: Context<MigrateBadgeSeeds>, quiz: u64) -> Result<()> {
+        instructions::migrate::migrate_badge_seeds(ctx, quiz)
+    }
+
+    pub fn migrate_reward_seeds(ctx: Context<MigrateRewardSeeds>, task: u16) -> Result<()> {
+        instructions::migrate::migrate_reward_seeds(ctx, task)
     }
 }
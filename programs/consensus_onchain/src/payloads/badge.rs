@@ -0,0 +1,24 @@
+use super::{from_json_str, Domain, Payload};
+use anchor_lang::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Signed by the backend for `upload_badge`.
+#[derive(AnchorSerialize, AnchorDeserialize, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BadgeInfo {
+    pub domain: Domain,
+    /// Unix time after which the signature is no longer accepted.
+    pub expires_at: i64,
+    /// Quiz id.
+    pub quiz: u64,
+    /// Tier of badge.
+    pub tier: u64,
+    /// Owner of badge.
+    pub owner: Pubkey,
+}
+
+impl Payload for BadgeInfo {
+    fn from_json(msg: &[u8]) -> Result<Self> {
+        from_json_str(msg)
+    }
+}
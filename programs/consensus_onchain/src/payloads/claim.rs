@@ -0,0 +1,24 @@
+use super::{from_json_str, Domain, Payload};
+use anchor_lang::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Signed by the backend for `claim`.
+#[derive(AnchorSerialize, AnchorDeserialize, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ClaimInput {
+    pub domain: Domain,
+    /// Unix time after which the signature is no longer accepted.
+    pub expires_at: i64,
+    pub task: u16,
+    pub nonce: u16,
+    pub reward: u64,
+    pub receiver: Pubkey,
+    /// Reward mint the claim pays out in.
+    pub mint: Pubkey,
+}
+
+impl Payload for ClaimInput {
+    fn from_json(msg: &[u8]) -> Result<Self> {
+        from_json_str(msg)
+    }
+}
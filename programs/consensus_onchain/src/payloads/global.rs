@@ -0,0 +1,43 @@
+use super::{from_json_str, parse_hash, Domain, Payload};
+use anchor_lang::prelude::*;
+use serde::Deserialize;
+
+/// Signed by the backend for `publish_global_consensus`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct GlobalRollup {
+    pub domain: Domain,
+    /// Unix time after which the signature is no longer accepted.
+    pub expires_at: i64,
+    /// Unix time the rolled-up epoch starts at.
+    pub epoch_start: u64,
+    /// Aggregate root of the epoch's consensus data.
+    pub root: [u8; 32],
+    pub contributors: u64,
+    pub dataset_ids: Vec<u64>,
+}
+
+/// JSON carries the root as a hex string, optionally `0x` prefixed.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonGlobalRollup {
+    domain: Domain,
+    expires_at: i64,
+    epoch_start: u64,
+    root: String,
+    contributors: u64,
+    dataset_ids: Vec<u64>,
+}
+
+impl Payload for GlobalRollup {
+    fn from_json(msg: &[u8]) -> Result<Self> {
+        let rollup: JsonGlobalRollup = from_json_str(msg)?;
+        Ok(GlobalRollup {
+            domain: rollup.domain,
+            expires_at: rollup.expires_at,
+            epoch_start: rollup.epoch_start,
+            root: parse_hash(&rollup.root)?,
+            contributors: rollup.contributors,
+            dataset_ids: rollup.dataset_ids,
+        })
+    }
+}
@@ -0,0 +1,120 @@
+/// Messages signed by the backend and verified on-chain.
+///
+/// Each payload has two encodings:
+/// - Borsh, prefixed with a version byte (`PAYLOAD_BORSH_V1`).
+/// - Legacy JSON, as produced by `JSON.stringify` (always starts with `{`).
+///
+/// The backend should build payloads with `encode` so both sides share the
+/// same definition.
+///
+/// Every payload starts with a `Domain` naming the program, the instruction
+/// and the deployment it was signed for, so a signature cannot be replayed
+/// against another instruction, another deployment or another cluster.
+pub mod badge;
+pub mod claim;
+pub mod global;
+pub mod validation;
+
+pub use badge::*;
+pub use claim::*;
+pub use global::*;
+pub use validation::*;
+
+use crate::errors::error::ErrorCode;
+use anchor_lang::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Version byte of the first Borsh layout.
+pub const PAYLOAD_BORSH_V1: u8 = 1;
+
+/// First byte of a legacy JSON payload.
+const PAYLOAD_JSON: u8 = b'{';
+
+/// Instruction kinds a payload can be signed for.
+pub const KIND_UPLOAD: u8 = 1;
+pub const KIND_BADGE: u8 = 2;
+pub const KIND_CLAIM: u8 = 3;
+// 4 was `claim_public`, replaced by `claim` with a `RewardMint`.
+pub const KIND_GLOBAL: u8 = 5;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Domain {
+    /// Program the payload is meant for.
+    pub program_id: Pubkey,
+    /// One of the `KIND_*` constants.
+    pub kind: u8,
+    /// Must equal `Config.deployment`, which tells clusters and
+    /// deployments sharing a signer apart.
+    pub deployment: u64,
+}
+
+impl Domain {
+    pub fn check(&self, kind: u8, deployment: u64) -> Result<()> {
+        require_keys_eq!(self.program_id, crate::ID, ErrorCode::DomainProgramMismatch);
+        require!(self.kind == kind, ErrorCode::DomainKindMismatch);
+        require!(
+            self.deployment == deployment,
+            ErrorCode::DomainDeploymentMismatch
+        );
+        Ok(())
+    }
+}
+
+/// Reject a payload whose `expires_at` (unix time) has passed, or lies further
+/// ahead than `max_validity` seconds. A `max_validity` of 0 disables the cap.
+pub fn check_expiry(expires_at: i64, max_validity: i64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= expires_at, ErrorCode::MessageExpired);
+    require!(
+        max_validity == 0 || expires_at - now <= max_validity,
+        ErrorCode::ValidityTooLong
+    );
+    Ok(())
+}
+
+pub trait Payload: AnchorSerialize + AnchorDeserialize {
+    /// Parse the legacy JSON encoding.
+    fn from_json(msg: &[u8]) -> Result<Self>;
+}
+
+/// Decode a signed message, picking the encoding from its first byte.
+pub fn decode<T: Payload>(msg: &[u8]) -> Result<T> {
+    match msg.first() {
+        Some(&PAYLOAD_BORSH_V1) => {
+            T::try_from_slice(&msg[1..]).map_err(|_| ErrorCode::MessageShouldBorsh.into())
+        }
+        Some(&PAYLOAD_JSON) => T::from_json(msg),
+        _ => Err(ErrorCode::UnsupportedPayloadVersion.into()),
+    }
+}
+
+/// Encode a payload in the current binary format, ready to be signed.
+pub fn encode<T: Payload>(payload: &T) -> Result<Vec<u8>> {
+    let mut msg = vec![PAYLOAD_BORSH_V1];
+    payload.serialize(&mut msg)?;
+    Ok(msg)
+}
+
+fn from_json_str<T: DeserializeOwned>(msg: &[u8]) -> Result<T> {
+    let msg_str = std::str::from_utf8(msg).map_err(|_| ErrorCode::MessageShouldString)?;
+    serde_json::from_str(msg_str).map_err(|_| ErrorCode::MessageShouldJson.into())
+}
+
+/// Parse a 32-byte hash given as hex, optionally `0x` prefixed.
+fn parse_hash(hex_str: &str) -> Result<[u8; 32]> {
+    let digits = if hex_str.to_lowercase().starts_with("0x") {
+        &hex_str[2..]
+    } else {
+        hex_str
+    };
+    let hex_data = hex::decode(digits).map_err(|_| ErrorCode::ProofDataError)?;
+    if hex_data.len() != 32 {
+        return Err(ErrorCode::ProofLengthError.into());
+    }
+    hex_data
+        .as_slice()
+        .try_into()
+        .map_err(|_| ErrorCode::ProofDataError.into())
+}
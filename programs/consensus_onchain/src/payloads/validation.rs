@@ -0,0 +1,36 @@
+use super::{from_json_str, parse_hash, Domain, Payload};
+use anchor_lang::prelude::*;
+use serde::Deserialize;
+
+/// Signed by the backend for `upload_validation`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Validation {
+    pub domain: Domain,
+    /// Unix time after which the signature is no longer accepted.
+    pub expires_at: i64,
+    pub timestamp: u64,
+    /// Rollup hash of the day's data.
+    pub consensus_proof: [u8; 32],
+}
+
+/// JSON carries the proof as a hex string, optionally `0x` prefixed.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonValidation {
+    domain: Domain,
+    expires_at: i64,
+    timestamp: u64,
+    consensus_proof: String,
+}
+
+impl Payload for Validation {
+    fn from_json(msg: &[u8]) -> Result<Self> {
+        let validation: JsonValidation = from_json_str(msg)?;
+        Ok(Validation {
+            domain: validation.domain,
+            expires_at: validation.expires_at,
+            timestamp: validation.timestamp,
+            consensus_proof: parse_hash(&validation.consensus_proof)?,
+        })
+    }
+}
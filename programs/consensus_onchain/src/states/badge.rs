@@ -3,24 +3,35 @@ use anchor_lang::prelude::*;
 #[account]
 #[derive(InitSpace)]
 pub struct BadgeConfigPool {
+    pub version: u8,
     /// Total numbers of badge.
     pub total: u64,
     /// Numbers of config.(aka quiz count)
     pub config_count: u64,
 }
 
+impl BadgeConfigPool {
+    pub const VERSION: u8 = 1;
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct BadgeConfig {
+    pub version: u8,
     /// Quiz id.
     pub quiz: u64,
     /// Numbers of badge.
     pub total: u64,
 }
 
+impl BadgeConfig {
+    pub const VERSION: u8 = 1;
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct Badge {
+    pub version: u8,
     /// Owner of badge.
     pub owner: Pubkey,
     /// Quiz id.
@@ -28,3 +39,7 @@ pub struct Badge {
     /// Tier of badge.
     pub tier: u64,
 }
+
+impl Badge {
+    pub const VERSION: u8 = 1;
+}
@@ -1,33 +1,21 @@
 use anchor_lang::prelude::*;
 use solana_program::pubkey::Pubkey;
 
+/// Authority of every reward vault.
 #[account]
+#[derive(InitSpace)]
 pub struct StateAccount {
-    /// The mint address of the usdt token to be distributed
-    pub token_mint: Pubkey,
-
-    /// The token vault that holds the usdt tokens to be distributed
-    pub token_vault: Pubkey,
+    pub version: u8,
 
     /// PDA bump seed
     pub bump: u8,
 
     /// Total claimed.
     pub claimed: u64,
-
-    /// The mint address of the public token to be distributed
-    pub public_token_mint: Pubkey,
-
-    /// The token vault that holds the public tokens to be distributed
-    pub public_token_vault: Pubkey,
 }
 
 impl StateAccount {
-    pub const LEN: usize = 32 + // token_mint
-        32 + // token_vault
-        1 +  // bump
-        8 + // claimed
-        64; // reserved
+    pub const VERSION: u8 = 1;
 }
 
 // #[account]
@@ -36,10 +24,43 @@ impl StateAccount {
 //     pub claimed: u64,
 // }
 
+/// A mint rewards can be claimed in, registered by the owner.
+#[account]
+#[derive(InitSpace)]
+pub struct RewardMint {
+    pub version: u8,
+    pub mint: Pubkey,
+    /// Token account holding the rewards, owned by the `state` PDA.
+    pub vault: Pubkey,
+    /// Bump of the `state` PDA that signs for the vault.
+    pub authority_bump: u8,
+    pub enabled: bool,
+    /// Set with `set_mint_paused` to stop claims in this mint during an
+    /// incident. Unlike `enabled`, the guardian can set it.
+    pub paused: bool,
+    /// Total sent to claimers.
+    pub claimed: u64,
+    /// Total moved out through `withdraw_tokens`.
+    pub withdrawn: u64,
+    pub bump: u8,
+}
+
+impl RewardMint {
+    pub const VERSION: u8 = 1;
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct ClaimReward {
+    pub version: u8,
     pub owner: Pubkey,
     pub reward: u64,
     pub times: u16,
+    /// Amount that reached the receiver on the last claim, after any
+    /// transfer fee.
+    pub received: u64,
+}
+
+impl ClaimReward {
+    pub const VERSION: u8 = 1;
 }
@@ -1,13 +1,73 @@
+use crate::errors::error::ErrorCode;
+use crate::states::signer::TrustedSigner;
 use anchor_lang::prelude::*;
+use solana_program::hash::hashv;
+
+/// Flags of `Config.paused`, one per group of instructions.
+pub const PAUSE_UPLOAD: u8 = 1;
+pub const PAUSE_BADGE: u8 = 1 << 1;
+pub const PAUSE_CLAIM: u8 = 1 << 2;
+// 1 << 3 paused public claims before reward mints moved to `RewardMint`,
+// which are paused one by one through `RewardMint.paused`.
+pub const PAUSE_WITHDRAW: u8 = 1 << 4;
+/// Stops `publish_global_consensus`.
+pub const PAUSE_GLOBAL: u8 = 1 << 5;
+pub const PAUSE_ALL: u8 = PAUSE_UPLOAD | PAUSE_BADGE | PAUSE_CLAIM | PAUSE_WITHDRAW | PAUSE_GLOBAL;
 
 #[account]
 #[derive(InitSpace)]
 pub struct Config {
-    pub signer: Pubkey,
+    /// Layout version, bumped whenever fields are added. Older accounts are
+    /// upgraded with the `migrate_*` instructions.
+    pub version: u8,
+    pub signer: TrustedSigner,
     pub owner: Pubkey,
     pub fee: u64, //upload consensus fee
     pub initialized: bool,
     pub bump: u8,
+    /// Owner proposed through `propose_owner`, waiting for `accept_owner`.
+    /// `Pubkey::default()` when no transfer is in progress.
+    pub pending_owner: Pubkey,
+    /// Slot from which `signer` is the trusted signer.
+    pub signer_since: u64,
+    /// How long a replaced signer's messages are still accepted, in slots.
+    pub signer_grace_slots: u64,
+    /// Deployment tag every signed payload must carry in its domain.
+    pub deployment: u64,
+    /// Longest time, in seconds, a signed payload may stay valid. 0 means
+    /// no limit.
+    pub max_validity: i64,
+    /// Claims paying more than this must be signed by `cosign_required`
+    /// distinct trusted signers.
+    pub cosign_threshold: u64,
+    /// Number of distinct signers needed above `cosign_threshold`, and on
+    /// every global publication. 1 means a single signature is always enough.
+    pub cosign_required: u8,
+    /// Bitmap of `PAUSE_*` flags.
+    pub paused: u8,
+    /// Key allowed to pause, but never to unpause. `Pubkey::default()` when
+    /// there is no guardian.
+    pub guardian: Pubkey,
+    /// Upload fees collected since initialization, in lamports.
+    pub fees_collected: u64,
+    /// Lamports withdrawn through `withdraw` since initialization.
+    pub total_withdrawn: u64,
+    /// Whether claims of a mint with a transfer fee send the fee on top of
+    /// the reward (`true`) or let the receiver get the reward minus the fee.
+    pub gross_up_transfer_fees: bool,
+    /// Set by the first `set_signer`. From then on the registry replaces
+    /// `signer`: only registry entries authorize signed messages, each for
+    /// its own scopes and slots.
+    pub signer_registry_active: bool,
+}
+
+impl Config {
+    pub const VERSION: u8 = 1;
+
+    pub fn check_not_paused(&self, flag: u8) -> Result<()> {
+        require!(self.paused & flag == 0, ErrorCode::Paused);
+        Ok(())
+    }
 }
 
 // impl Config {
@@ -19,8 +79,135 @@ pub struct Config {
 #[account]
 #[derive(InitSpace)]
 pub struct ConsensusState {
+    pub version: u8,
+    /// Set on uploads made by the trusted signer itself, which served as the
+    /// global record before `GlobalConsensus`. Only carried over from older
+    /// accounts; new uploads leave it false.
     pub global: bool,
     pub timestamp: u64,
     pub consensus_proof: [u8; 32],
     pub bump: u8,
 }
+
+impl ConsensusState {
+    pub const VERSION: u8 = 1;
+}
+
+/// `UploadWindow` used until the owner sets one: one upload per UTC day,
+/// for a day at most a week back.
+pub const DEFAULT_EPOCH_LENGTH: u64 = 24 * 60 * 60;
+pub const DEFAULT_MAX_PAST: i64 = 7 * 24 * 60 * 60;
+pub const DEFAULT_MAX_FUTURE: i64 = 5 * 60;
+
+/// Which timestamps `upload_validation` accepts. Timestamps are normalized
+/// to the start of their epoch and a user has one `ConsensusState` per epoch.
+#[account]
+#[derive(InitSpace)]
+pub struct UploadWindow {
+    pub version: u8,
+    /// Length of an upload epoch, in seconds.
+    pub epoch_length: u64,
+    /// How far, in seconds, an upload's timestamp may lie before or after
+    /// `Clock::unix_timestamp`.
+    pub max_past: i64,
+    pub max_future: i64,
+    pub bump: u8,
+}
+
+impl UploadWindow {
+    pub const VERSION: u8 = 1;
+
+    /// Fill in the defaults if the owner never set the window.
+    pub fn init_defaults(&mut self, bump: u8) {
+        if self.version == 0 {
+            self.version = Self::VERSION;
+            self.epoch_length = DEFAULT_EPOCH_LENGTH;
+            self.max_past = DEFAULT_MAX_PAST;
+            self.max_future = DEFAULT_MAX_FUTURE;
+            self.bump = bump;
+        }
+    }
+
+    /// Index of the epoch `timestamp` falls in. Also used in account
+    /// constraints, before `init_defaults` has run.
+    pub fn epoch(&self, timestamp: u64) -> u64 {
+        let epoch_length = if self.version == 0 {
+            DEFAULT_EPOCH_LENGTH
+        } else {
+            self.epoch_length
+        };
+        timestamp / epoch_length
+    }
+
+    /// Shortest retention that keeps a closed `ConsensusState` from being
+    /// uploaded again: a record stores the start of its epoch, and an upload
+    /// for any time in that epoch is refused once it is more than `max_past`
+    /// old.
+    pub fn min_retention(&self) -> i64 {
+        i64::try_from(self.epoch_length)
+            .unwrap_or(i64::MAX)
+            .saturating_add(self.max_past)
+    }
+
+    pub fn check(&self, timestamp: u64, now: i64) -> Result<()> {
+        let timestamp = i64::try_from(timestamp).map_err(|_| ErrorCode::TimestampOutOfWindow)?;
+        require!(
+            now.saturating_sub(timestamp) <= self.max_past
+                && timestamp.saturating_sub(now) <= self.max_future,
+            ErrorCode::TimestampOutOfWindow
+        );
+        Ok(())
+    }
+}
+
+/// Hash chain over every closed `ConsensusState`, so proofs stay verifiable
+/// after their accounts are gone: replaying the `ConsensusClosed` events
+/// must reproduce `root`.
+#[account]
+#[derive(InitSpace)]
+pub struct ConsensusArchive {
+    pub version: u8,
+    /// Seconds after its `timestamp` a `ConsensusState` may be closed.
+    pub retention: i64,
+    /// Number of records folded into `root`.
+    pub count: u64,
+    pub root: [u8; 32],
+    pub bump: u8,
+}
+
+impl ConsensusArchive {
+    pub const VERSION: u8 = 1;
+
+    /// Check that `state` is past retention. `min_retention` comes from the
+    /// current `UploadWindow` and wins if the window grew since the
+    /// retention was set.
+    pub fn check_retention(
+        &self,
+        state: &ConsensusState,
+        now: i64,
+        min_retention: i64,
+    ) -> Result<()> {
+        let timestamp =
+            i64::try_from(state.timestamp).map_err(|_| ErrorCode::RetentionNotElapsed)?;
+        let retention = self.retention.max(min_retention);
+        require!(
+            now >= timestamp.saturating_add(retention),
+            ErrorCode::RetentionNotElapsed
+        );
+        Ok(())
+    }
+
+    /// Fold a closed record into the chain:
+    /// `root = sha256(root || user || timestamp || consensus_proof || global)`.
+    pub fn append(&mut self, user: &Pubkey, state: &ConsensusState) {
+        self.root = hashv(&[
+            &self.root,
+            user.as_ref(),
+            &state.timestamp.to_le_bytes(),
+            &state.consensus_proof,
+            &[u8::from(state.global)],
+        ])
+        .to_bytes();
+        self.count += 1;
+    }
+}
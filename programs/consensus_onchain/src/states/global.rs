@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+pub const MAX_DATASETS: usize = 16;
+
+/// One epoch's rollup over all uploads, published by the trusted signers.
+#[account]
+#[derive(InitSpace)]
+pub struct GlobalConsensus {
+    pub version: u8,
+    /// Unix time the epoch starts at. Publications are keyed and ordered by
+    /// it, so they stay in order when `UploadWindow.epoch_length` changes.
+    pub epoch_start: u64,
+    /// `UploadWindow.epoch_length` at publication.
+    pub epoch_length: u64,
+    /// Aggregate root of the epoch's consensus data.
+    pub root: [u8; 32],
+    pub contributors: u64,
+    #[max_len(MAX_DATASETS)]
+    pub dataset_ids: Vec<u64>,
+    /// Epoch start and root of the publication before this one. Both are
+    /// zero for the first publication.
+    pub prev_epoch_start: u64,
+    pub prev_root: [u8; 32],
+    pub published_at: i64,
+    pub bump: u8,
+}
+
+impl GlobalConsensus {
+    pub const VERSION: u8 = 1;
+}
+
+/// Latest `GlobalConsensus`, which the next publication links to.
+#[account]
+#[derive(InitSpace)]
+pub struct GlobalConsensusHead {
+    pub version: u8,
+    pub epoch_start: u64,
+    pub root: [u8; 32],
+    /// Number of epochs published.
+    pub count: u64,
+    pub bump: u8,
+}
+
+impl GlobalConsensusHead {
+    pub const VERSION: u8 = 1;
+}
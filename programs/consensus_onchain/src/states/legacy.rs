@@ -0,0 +1,61 @@
+//! Layouts accounts had before they carried a `version` byte. The
+//! `migrate_*` instructions read them and rewrite the account in the current
+//! layout.
+// Some fields only exist to keep the layout and are not carried over.
+#![allow(dead_code)]
+
+use anchor_lang::prelude::*;
+
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct ConfigV0 {
+    pub signer: Pubkey,
+    pub owner: Pubkey,
+    pub fee: u64,
+    pub initialized: bool,
+    pub bump: u8,
+}
+
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct ConsensusStateV0 {
+    pub global: bool,
+    pub timestamp: u64,
+    pub consensus_proof: [u8; 32],
+    pub bump: u8,
+}
+
+/// `migrate_state` turns the USDT and PUBLIC slots into `RewardMint`s.
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct StateAccountV0 {
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub bump: u8,
+    pub claimed: u64,
+    pub public_token_mint: Pubkey,
+    pub public_token_vault: Pubkey,
+}
+
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct ClaimRewardV0 {
+    pub owner: Pubkey,
+    pub reward: u64,
+    pub times: u16,
+}
+
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct BadgeConfigPoolV0 {
+    pub total: u64,
+    pub config_count: u64,
+}
+
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct BadgeConfigV0 {
+    pub quiz: u64,
+    pub total: u64,
+}
+
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct BadgeV0 {
+    pub owner: Pubkey,
+    pub quiz: u64,
+    pub tier: u64,
+}
@@ -0,0 +1,125 @@
+use crate::errors::error::ErrorCode;
+use anchor_lang::prelude::*;
+
+/// Length of the per-user claim window, in seconds.
+pub const USER_WINDOW_SECONDS: i64 = 24 * 60 * 60;
+
+/// Length of one bucket of the per-user window, in seconds.
+pub const USER_BUCKET_SECONDS: i64 = 60 * 60;
+
+/// Buckets covering a full window plus the current, partial one.
+pub const USER_WINDOW_BUCKETS: usize = (USER_WINDOW_SECONDS / USER_BUCKET_SECONDS) as usize + 1;
+
+/// Claim limits for one reward mint, set by the owner. A limit of 0 means
+/// no limit.
+#[account]
+#[derive(InitSpace)]
+pub struct MintLimits {
+    pub version: u8,
+    pub mint: Pubkey,
+    /// Largest amount a single claim may pay.
+    pub max_per_claim: u64,
+    /// Largest amount one user may claim per `USER_WINDOW_SECONDS`.
+    pub user_window_cap: u64,
+    /// Largest amount all users together may claim per Solana cluster epoch
+    /// (`Clock::epoch`, a few days long and varying by cluster), not per
+    /// upload epoch of `UploadWindow`.
+    pub epoch_cap: u64,
+    /// Cluster epoch `epoch_claimed` belongs to.
+    pub epoch: u64,
+    pub epoch_claimed: u64,
+    pub bump: u8,
+}
+
+/// How much one user claimed of one mint recently, in hourly buckets. A
+/// claim counts until its whole bucket is more than `USER_WINDOW_SECONDS`
+/// old, 24 to 25 hours after it was made, so no span of
+/// `USER_WINDOW_SECONDS` pays the user more than the cap.
+#[account]
+#[derive(InitSpace)]
+pub struct UserUsage {
+    pub version: u8,
+    /// Index (`unix time / USER_BUCKET_SECONDS`) of the newest bucket.
+    pub last_bucket: i64,
+    /// Amount claimed per bucket, indexed by bucket index modulo
+    /// `USER_WINDOW_BUCKETS`.
+    pub buckets: [u64; USER_WINDOW_BUCKETS],
+    pub bump: u8,
+}
+
+impl UserUsage {
+    pub const VERSION: u8 = 1;
+
+    /// Move the window forward to `now`, clearing the buckets that left it.
+    /// Returns the slot of the current bucket.
+    fn advance(&mut self, now: i64) -> usize {
+        let bucket = now.div_euclid(USER_BUCKET_SECONDS);
+        let elapsed = bucket.saturating_sub(self.last_bucket);
+        if elapsed >= USER_WINDOW_BUCKETS as i64 {
+            self.buckets = [0; USER_WINDOW_BUCKETS];
+        } else {
+            for i in 1..=elapsed {
+                let slot = (self.last_bucket + i).rem_euclid(USER_WINDOW_BUCKETS as i64);
+                self.buckets[slot as usize] = 0;
+            }
+        }
+        if bucket > self.last_bucket {
+            self.last_bucket = bucket;
+        }
+        self.last_bucket.rem_euclid(USER_WINDOW_BUCKETS as i64) as usize
+    }
+
+    /// Total claimed over the window.
+    pub fn window_claimed(&self) -> u64 {
+        self.buckets
+            .iter()
+            .fold(0, |total, amount| total.saturating_add(*amount))
+    }
+}
+
+impl MintLimits {
+    pub const VERSION: u8 = 1;
+
+    /// Check `amount` against every limit and record it in the usage
+    /// counters. The user window rolls per bucket; the epoch counter resets
+    /// on the first claim of a new cluster epoch, `epoch` being `Clock::epoch`.
+    pub fn record(
+        &mut self,
+        usage: &mut UserUsage,
+        amount: u64,
+        now: i64,
+        epoch: u64,
+    ) -> Result<()> {
+        require!(
+            self.max_per_claim == 0 || amount <= self.max_per_claim,
+            ErrorCode::ClaimAmountTooLarge
+        );
+
+        let slot = usage.advance(now);
+        let window_claimed = usage
+            .window_claimed()
+            .checked_add(amount)
+            .ok_or(ErrorCode::UserClaimCapExceeded)?;
+        require!(
+            self.user_window_cap == 0 || window_claimed <= self.user_window_cap,
+            ErrorCode::UserClaimCapExceeded
+        );
+
+        if epoch != self.epoch {
+            self.epoch = epoch;
+            self.epoch_claimed = 0;
+        }
+        let epoch_claimed = self
+            .epoch_claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::EpochClaimCapExceeded)?;
+        require!(
+            self.epoch_cap == 0 || epoch_claimed <= self.epoch_cap,
+            ErrorCode::EpochClaimCapExceeded
+        );
+
+        usage.buckets[slot] = usage.buckets[slot].saturating_add(amount);
+        self.epoch_claimed = epoch_claimed;
+        Ok(())
+    }
+}
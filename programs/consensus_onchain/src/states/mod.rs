@@ -1,3 +1,8 @@
 pub mod badge;
 pub mod claim;
 pub mod consensus;
+pub mod global;
+pub mod legacy;
+pub mod limits;
+pub mod profile;
+pub mod signer;
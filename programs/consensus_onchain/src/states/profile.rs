@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+
+pub const MAX_PROFILE_MINTS: usize = 8;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct MintClaimed {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// A user's participation, readable in one fetch.
+#[account]
+#[derive(InitSpace)]
+pub struct ContributorProfile {
+    pub version: u8,
+    pub user: Pubkey,
+    pub total_uploads: u64,
+    /// Consensus timestamps of the earliest and latest upload.
+    pub first_upload: u64,
+    pub last_upload: u64,
+    /// Consecutive upload epochs with an upload, ending at `last_upload`.
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub badges: u64,
+    /// Amount received per reward mint, after transfer fees, for the first
+    /// `MAX_PROFILE_MINTS` mints claimed.
+    #[max_len(MAX_PROFILE_MINTS)]
+    pub claimed: Vec<MintClaimed>,
+    pub bump: u8,
+}
+
+impl ContributorProfile {
+    pub const VERSION: u8 = 1;
+
+    /// Count an upload for the consensus `timestamp`, with epochs of
+    /// `epoch_length` seconds. Uploads for an epoch before `last_upload`
+    /// count towards the total but not the streak.
+    pub fn record_upload(&mut self, timestamp: u64, epoch_length: u64) {
+        let epoch = timestamp / epoch_length;
+        if self.total_uploads == 0 {
+            self.first_upload = timestamp;
+            self.last_upload = timestamp;
+            self.current_streak = 1;
+        } else {
+            let last_epoch = self.last_upload / epoch_length;
+            if epoch == last_epoch + 1 {
+                self.current_streak += 1;
+            } else if epoch > last_epoch + 1 {
+                self.current_streak = 1;
+            }
+            self.first_upload = self.first_upload.min(timestamp);
+            self.last_upload = self.last_upload.max(timestamp);
+        }
+        self.longest_streak = self.longest_streak.max(self.current_streak);
+        self.total_uploads += 1;
+    }
+
+    /// Add `amount` to the total of `mint`. Once `MAX_PROFILE_MINTS` mints
+    /// are tracked, further mints are left out rather than failing the
+    /// claim.
+    pub fn record_claim(&mut self, mint: Pubkey, amount: u64) {
+        if let Some(entry) = self.claimed.iter_mut().find(|entry| entry.mint == mint) {
+            entry.amount = entry.amount.saturating_add(amount);
+        } else if self.claimed.len() < MAX_PROFILE_MINTS {
+            self.claimed.push(MintClaimed { mint, amount });
+        }
+    }
+}
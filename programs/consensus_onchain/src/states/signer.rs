@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+
+/// Maximum number of keys the registry can hold.
+pub const MAX_SIGNERS: usize = 10;
+
+/// Number of retired signers kept in `SignerHistory`.
+pub const MAX_SIGNER_HISTORY: usize = 8;
+
+/// Scope bits, one per instruction that accepts a signed message.
+pub const SCOPE_UPLOAD: u8 = 1 << 0;
+pub const SCOPE_BADGE: u8 = 1 << 1;
+pub const SCOPE_CLAIM: u8 = 1 << 2;
+// 1 << 3 was `claim_public`, replaced by `claim` with a `RewardMint`.
+pub const SCOPE_GLOBAL: u8 = 1 << 4;
+pub const SCOPE_ALL: u8 = SCOPE_UPLOAD | SCOPE_BADGE | SCOPE_CLAIM | SCOPE_GLOBAL;
+
+/// Scopes `verify_cosigners` applies the co-signing policy to.
+pub const COSIGNED_SCOPES: [u8; 2] = [SCOPE_CLAIM, SCOPE_GLOBAL];
+
+/// A key whose signatures the program trusts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum TrustedSigner {
+    /// Verified through the Ed25519Program.
+    Ed25519(Pubkey),
+    /// 20-byte Ethereum address, verified through the Secp256k1Program.
+    Ethereum([u8; 20]),
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct SignerEntry {
+    /// Key of the signing node.
+    pub key: TrustedSigner,
+    /// Disabled keys are kept for bookkeeping but never accepted.
+    pub enabled: bool,
+    /// First slot the key is valid for.
+    pub valid_from: u64,
+    /// Last slot the key is valid for, 0 means no end.
+    pub valid_until: u64,
+    /// Bitmap of `SCOPE_*` the key may authorize.
+    pub scope: u8,
+}
+
+impl SignerEntry {
+    pub fn is_valid(&self, scope: u8, slot: u64) -> bool {
+        self.enabled
+            && self.scope & scope == scope
+            && slot >= self.valid_from
+            && (self.valid_until == 0 || slot <= self.valid_until)
+    }
+}
+
+/// Backend keys trusted in addition to `Config.signer`.
+#[account]
+#[derive(InitSpace)]
+pub struct SignerRegistry {
+    pub version: u8,
+    #[max_len(MAX_SIGNERS)]
+    pub signers: Vec<SignerEntry>,
+    pub bump: u8,
+}
+
+impl SignerRegistry {
+    pub const VERSION: u8 = 1;
+
+    pub fn position(&self, key: &TrustedSigner) -> Option<usize> {
+        self.signers.iter().position(|entry| entry.key == *key)
+    }
+
+    pub fn is_authorized(&self, key: &TrustedSigner, scope: u8, slot: u64) -> bool {
+        self.signers
+            .iter()
+            .any(|entry| entry.key == *key && entry.is_valid(scope, slot))
+    }
+
+    /// Number of keys that may authorize `scope` at `slot`. Keys not valid
+    /// yet do not count: until they are, they cannot sign.
+    pub fn usable_for(&self, scope: u8, slot: u64) -> usize {
+        self.signers
+            .iter()
+            .filter(|entry| entry.is_valid(scope, slot))
+            .count()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RetiredSigner {
+    pub key: TrustedSigner,
+    /// Slot the key became the trusted signer.
+    pub valid_from: u64,
+    /// Slot the key was replaced by `update`.
+    pub retired_at: u64,
+    /// Last slot the key is still accepted, fixed at rotation from the
+    /// `Config.signer_grace_slots` then in force.
+    pub grace_until: u64,
+    /// Bitmap of `SCOPE_*` the key could authorize when it was retired.
+    pub scope: u8,
+}
+
+/// Previous values of `Config.signer`, newest last.
+#[account]
+#[derive(InitSpace)]
+pub struct SignerHistory {
+    pub version: u8,
+    #[max_len(MAX_SIGNER_HISTORY)]
+    pub signers: Vec<RetiredSigner>,
+    pub bump: u8,
+}
+
+impl SignerHistory {
+    pub const VERSION: u8 = 1;
+
+    /// Record a retired signer, dropping the oldest entry when full.
+    pub fn push(&mut self, signer: RetiredSigner) {
+        if self.signers.len() >= MAX_SIGNER_HISTORY {
+            self.signers.remove(0);
+        }
+        self.signers.push(signer);
+    }
+
+    /// A retired signer is still accepted up to its `grace_until` slot, for
+    /// the scopes it held when it was retired.
+    pub fn in_grace(&self, key: &TrustedSigner, scope: u8, slot: u64) -> bool {
+        self.signers.iter().any(|entry| {
+            entry.key == *key && entry.scope & scope == scope && slot <= entry.grace_until
+        })
+    }
+}
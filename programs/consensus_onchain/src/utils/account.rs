@@ -0,0 +1,12 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+/// Whether `account` holds a `T` written by this program. Legacy seed
+/// guards use it so that an empty address, or another account type that
+/// happens to share the old seeds, does not count as unmigrated.
+pub fn holds_account<T: Discriminator>(account: &AccountInfo) -> bool {
+    account.owner == &crate::ID
+        && account
+            .try_borrow_data()
+            .is_ok_and(|data| data.starts_with(&T::DISCRIMINATOR))
+}
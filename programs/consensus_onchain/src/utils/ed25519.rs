@@ -2,74 +2,98 @@
 use crate::errors::error::ErrorCode;
 use anchor_lang::prelude::*;
 use solana_program::ed25519_program::ID as ED25519_ID;
-use solana_program::instruction::Instruction;
+use solana_program::sysvar::instructions::load_instruction_at_checked;
 
-use std::convert::TryInto;
+// Layout used by the Ed25519Program
+// https://github.com/solana-labs/solana-web3.js/blob/master/src/ed25519-program.ts#L33
+//
+// Byte 0 holds the number of signatures and byte 1 is padding. Then, for
+// every signature, seven u16 values:
+// signature_offset, signature_instruction_index, public_key_offset,
+// public_key_instruction_index, message_data_offset, message_data_size,
+// message_instruction_index.
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_SERIALIZED_SIZE: usize = 14;
+const PUBKEY_SERIALIZED_SIZE: usize = 32;
+const SIGNATURE_SERIALIZED_SIZE: usize = 64;
 
-/// Verify Ed25519Program instruction fields
-pub fn verify_ed25519_ix(ix: &Instruction, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<()> {
-    if ix.program_id       != ED25519_ID                   ||  // The program id we expect
-        ix.accounts.len()   != 0                            ||  // With no context accounts
-        ix.data.len()       != (16 + 64 + 32 + msg.len())
-    // And data of this size
-    {
-        return Err(ErrorCode::SigVerificationFailed.into()); // Otherwise, we can already throw err
-    }
-
-    check_ed25519_data(&ix.data, pubkey, msg, sig)?; // If that's not the case, check data
-
-    Ok(())
+/// One signature checked by an Ed25519Program instruction.
+pub struct Ed25519Signature {
+    pub pubkey: Pubkey,
+    pub signature: [u8; 64],
+    pub message: Vec<u8>,
 }
 
-/// Verify serialized Ed25519Program instruction data
-pub fn check_ed25519_data(data: &[u8], pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<()> {
-    // According to this layout used by the Ed25519Program
-    // https://github.com/solana-labs/solana-web3.js/blob/master/src/ed25519-program.ts#L33
-
-    // "Deserializing" byte slices
-
-    let num_signatures = &[data[0]]; // Byte  0
-    let padding = &[data[1]]; // Byte  1
-    let signature_offset = &data[2..=3]; // Bytes 2,3
-    let signature_instruction_index = &data[4..=5]; // Bytes 4,5
-    let public_key_offset = &data[6..=7]; // Bytes 6,7
-    let public_key_instruction_index = &data[8..=9]; // Bytes 8,9
-    let message_data_offset = &data[10..=11]; // Bytes 10,11
-    let message_data_size = &data[12..=13]; // Bytes 12,13
-    let message_instruction_index = &data[14..=15]; // Bytes 14,15
-
-    let data_pubkey = &data[16..16 + 32]; // Bytes 16..16+32
-    let data_sig = &data[48..48 + 64]; // Bytes 48..48+64
-    let data_msg = &data[112..]; // Bytes 112..end
-
-    // Expected values
+/// Collect the signatures of every Ed25519Program instruction in the
+/// transaction, wherever they sit and however many entries they hold.
+///
+/// The runtime verifies precompile instructions before the program runs,
+/// so every returned signature is known to be valid.
+pub fn load_ed25519_signatures(
+    ix_sysvar: &AccountInfo,
+    count: u16,
+) -> Result<Vec<Ed25519Signature>> {
+    let mut signatures = Vec::new();
+    for index in 0..count {
+        let ix = load_instruction_at_checked(index.into(), ix_sysvar)?;
+        if ix.program_id == ED25519_ID {
+            check_ed25519_data(&ix.data, ix_sysvar, &mut signatures)?;
+        }
+    }
+    Ok(signatures)
+}
 
-    let exp_public_key_offset: u16 = 16; // 2*u8 + 7*u16
-    let exp_signature_offset: u16 = exp_public_key_offset + pubkey.len() as u16;
-    let exp_message_data_offset: u16 = exp_signature_offset + sig.len() as u16;
-    let exp_num_signatures: u8 = 1;
-    let exp_message_data_size: u16 = msg.len().try_into().unwrap();
+/// Parse serialized Ed25519Program instruction data, resolving offsets
+/// that point into other instructions of the transaction.
+pub fn check_ed25519_data(
+    data: &[u8],
+    ix_sysvar: &AccountInfo,
+    signatures: &mut Vec<Ed25519Signature>,
+) -> Result<()> {
+    let num_signatures = *data
+        .first()
+        .ok_or(ErrorCode::MalformedSignatureInstruction)? as usize;
 
-    // Header and Arg Checks
+    for i in 0..num_signatures {
+        let start = SIGNATURE_OFFSETS_START + i * SIGNATURE_OFFSETS_SERIALIZED_SIZE;
+        let offsets = data
+            .get(start..start + SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+            .ok_or(ErrorCode::MalformedSignatureInstruction)?;
+        let read = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]);
 
-    // Header
-    if num_signatures != &exp_num_signatures.to_le_bytes()
-        || padding != &[0]
-        || signature_offset != &exp_signature_offset.to_le_bytes()
-        || signature_instruction_index != &u16::MAX.to_le_bytes()
-        || public_key_offset != &exp_public_key_offset.to_le_bytes()
-        || public_key_instruction_index != &u16::MAX.to_le_bytes()
-        || message_data_offset != &exp_message_data_offset.to_le_bytes()
-        || message_data_size != &exp_message_data_size.to_le_bytes()
-        || message_instruction_index != &u16::MAX.to_le_bytes()
-    {
-        return Err(ErrorCode::SigVerificationFailed.into());
-    }
+        let signature = load_slice(data, ix_sysvar, read(2), read(0), SIGNATURE_SERIALIZED_SIZE)?;
+        let pubkey = load_slice(data, ix_sysvar, read(6), read(4), PUBKEY_SERIALIZED_SIZE)?;
+        let message = load_slice(data, ix_sysvar, read(12), read(8), read(10).into())?;
 
-    // Arguments
-    if data_pubkey != pubkey || data_msg != msg || data_sig != sig {
-        return Err(ErrorCode::SigVerificationFailed.into());
+        signatures.push(Ed25519Signature {
+            pubkey: Pubkey::try_from(pubkey.as_slice())
+                .map_err(|_| ErrorCode::MalformedSignatureInstruction)?,
+            signature: signature
+                .as_slice()
+                .try_into()
+                .map_err(|_| ErrorCode::MalformedSignatureInstruction)?,
+            message,
+        });
     }
 
     Ok(())
 }
+
+/// Read `len` bytes at `offset`, either from `data` itself (index
+/// `u16::MAX`) or from the instruction at `instruction_index`.
+fn load_slice(
+    data: &[u8],
+    ix_sysvar: &AccountInfo,
+    instruction_index: u16,
+    offset: u16,
+    len: usize,
+) -> Result<Vec<u8>> {
+    let start = offset as usize;
+    let bytes = if instruction_index == u16::MAX {
+        data.get(start..start + len).map(<[u8]>::to_vec)
+    } else {
+        let ix = load_instruction_at_checked(instruction_index.into(), ix_sysvar)?;
+        ix.data.get(start..start + len).map(<[u8]>::to_vec)
+    };
+    bytes.ok_or(ErrorCode::MalformedSignatureInstruction.into())
+}
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use solana_program::{hash, keccak};
+
+/// Longest Merkle path `verify_inclusion` accepts.
+pub const MAX_PROOF_DEPTH: usize = 32;
+
+/// Hash function a Merkle tree was built with.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MerkleHash {
+    Sha256,
+    Keccak256,
+}
+
+impl MerkleHash {
+    fn hashv(self, parts: &[&[u8]]) -> [u8; 32] {
+        match self {
+            MerkleHash::Sha256 => hash::hashv(parts).to_bytes(),
+            MerkleHash::Keccak256 => keccak::hashv(parts).to_bytes(),
+        }
+    }
+}
+
+/// Prefixes that keep leaves and inner nodes apart, so an inner node cannot
+/// be passed off as a leaf.
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Root reached from `leaf` along `path`. The leaf is hashed as
+/// `H(0x00 || leaf)` and each pair of nodes as `H(0x01 || min || max)`,
+/// in sorted order so the path needs no left/right flags.
+pub fn merkle_root(leaf: [u8; 32], path: &[[u8; 32]], hash: MerkleHash) -> [u8; 32] {
+    let leaf = hash.hashv(&[LEAF_PREFIX, &leaf]);
+    path.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hash.hashv(&[NODE_PREFIX, &node, sibling])
+        } else {
+            hash.hashv(&[NODE_PREFIX, sibling, &node])
+        }
+    })
+}
@@ -0,0 +1,96 @@
+use crate::errors::error::ErrorCode;
+use anchor_lang::prelude::*;
+use solana_program::secp256k1_program::ID as SECP256K1_ID;
+use solana_program::sysvar::instructions::load_instruction_at_checked;
+
+// Layout used by the Secp256k1Program
+// https://github.com/solana-labs/solana-web3.js/blob/master/src/secp256k1-program.ts
+//
+// Byte 0 holds the number of signatures. Then, for every signature:
+// signature_offset (u16), signature_instruction_index (u8),
+// eth_address_offset (u16), eth_address_instruction_index (u8),
+// message_data_offset (u16), message_data_size (u16),
+// message_instruction_index (u8).
+// Instruction indices are absolute positions in the transaction.
+const SIGNATURE_OFFSETS_START: usize = 1;
+const SIGNATURE_OFFSETS_SERIALIZED_SIZE: usize = 11;
+const ETH_ADDRESS_SERIALIZED_SIZE: usize = 20;
+const SIGNATURE_SERIALIZED_SIZE: usize = 64;
+
+/// One signature checked by a Secp256k1Program instruction. The precompile
+/// recovers the signer of `keccak256(message)` and compares it with
+/// `eth_address`.
+pub struct Secp256k1Signature {
+    pub eth_address: [u8; 20],
+    /// Signature without the trailing recovery id.
+    pub signature: [u8; 64],
+    pub message: Vec<u8>,
+}
+
+/// Collect the signatures of every Secp256k1Program instruction in the
+/// transaction, as `load_ed25519_signatures` does for Ed25519.
+pub fn load_secp256k1_signatures(
+    ix_sysvar: &AccountInfo,
+    count: u16,
+) -> Result<Vec<Secp256k1Signature>> {
+    let mut signatures = Vec::new();
+    for index in 0..count {
+        let ix = load_instruction_at_checked(index.into(), ix_sysvar)?;
+        if ix.program_id == SECP256K1_ID {
+            check_secp256k1_data(&ix.data, ix_sysvar, &mut signatures)?;
+        }
+    }
+    Ok(signatures)
+}
+
+/// Parse serialized Secp256k1Program instruction data.
+pub fn check_secp256k1_data(
+    data: &[u8],
+    ix_sysvar: &AccountInfo,
+    signatures: &mut Vec<Secp256k1Signature>,
+) -> Result<()> {
+    let num_signatures = *data
+        .first()
+        .ok_or(ErrorCode::MalformedSignatureInstruction)? as usize;
+
+    for i in 0..num_signatures {
+        let start = SIGNATURE_OFFSETS_START + i * SIGNATURE_OFFSETS_SERIALIZED_SIZE;
+        let offsets = data
+            .get(start..start + SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+            .ok_or(ErrorCode::MalformedSignatureInstruction)?;
+        let read = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]);
+
+        let signature = load_slice(ix_sysvar, offsets[2], read(0), SIGNATURE_SERIALIZED_SIZE)?;
+        let eth_address = load_slice(ix_sysvar, offsets[5], read(3), ETH_ADDRESS_SERIALIZED_SIZE)?;
+        let message = load_slice(ix_sysvar, offsets[10], read(6), read(8).into())?;
+
+        signatures.push(Secp256k1Signature {
+            eth_address: eth_address
+                .as_slice()
+                .try_into()
+                .map_err(|_| ErrorCode::MalformedSignatureInstruction)?,
+            signature: signature
+                .as_slice()
+                .try_into()
+                .map_err(|_| ErrorCode::MalformedSignatureInstruction)?,
+            message,
+        });
+    }
+
+    Ok(())
+}
+
+/// Read `len` bytes at `offset` from the instruction at `instruction_index`.
+fn load_slice(
+    ix_sysvar: &AccountInfo,
+    instruction_index: u8,
+    offset: u16,
+    len: usize,
+) -> Result<Vec<u8>> {
+    let start = offset as usize;
+    let ix = load_instruction_at_checked(instruction_index.into(), ix_sysvar)?;
+    ix.data
+        .get(start..start + len)
+        .map(<[u8]>::to_vec)
+        .ok_or(ErrorCode::MalformedSignatureInstruction.into())
+}
@@ -0,0 +1,154 @@
+use crate::errors::error::ErrorCode;
+use crate::states::consensus::Config;
+use crate::states::signer::{SignerHistory, SignerRegistry, TrustedSigner, COSIGNED_SCOPES};
+use crate::utils::{load_ed25519_signatures, load_secp256k1_signatures};
+use anchor_lang::prelude::*;
+
+/// A signature verified by one of the signature precompiles.
+pub struct VerifiedSignature {
+    pub signer: TrustedSigner,
+    pub signature: [u8; 64],
+    pub message: Vec<u8>,
+}
+
+/// Collect every Ed25519 and Secp256k1 signature verified in the transaction.
+pub fn load_signatures(ix_sysvar: &AccountInfo) -> Result<Vec<VerifiedSignature>> {
+    let count = instruction_count(ix_sysvar)?;
+    let ed25519 = load_ed25519_signatures(ix_sysvar, count)?
+        .into_iter()
+        .map(|entry| VerifiedSignature {
+            signer: TrustedSigner::Ed25519(entry.pubkey),
+            signature: entry.signature,
+            message: entry.message,
+        });
+    let secp256k1 = load_secp256k1_signatures(ix_sysvar, count)?
+        .into_iter()
+        .map(|entry| VerifiedSignature {
+            signer: TrustedSigner::Ethereum(entry.eth_address),
+            signature: entry.signature,
+            message: entry.message,
+        });
+    Ok(ed25519.chain(secp256k1).collect())
+}
+
+/// Number of instructions in the transaction, stored in the first two bytes
+/// of the Instructions sysvar.
+fn instruction_count(ix_sysvar: &AccountInfo) -> Result<u16> {
+    let data = ix_sysvar.try_borrow_data()?;
+    let bytes = data
+        .get(0..2)
+        .ok_or(ErrorCode::MalformedSignatureInstruction)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+/// Check that a signature precompile anywhere in the transaction verified
+/// `sig` over `msg`, made by a trusted key (see `is_trusted`) or by a retired
+/// signer still inside its grace period. For Ethereum signers `sig` is the
+/// signature without its recovery id. Unauthorized entries over the same
+/// message and signature are skipped.
+///
+/// Returns the key that signed the message.
+pub fn verify_signer(
+    signatures: &[VerifiedSignature],
+    config: &Config,
+    registry: Option<&SignerRegistry>,
+    history: Option<&SignerHistory>,
+    scope: u8,
+    msg: &[u8],
+    sig: &[u8],
+) -> Result<TrustedSigner> {
+    let slot = Clock::get()?.slot;
+    let mut matched = false;
+    for entry in signatures
+        .iter()
+        .filter(|entry| entry.signature[..] == *sig && entry.message == msg)
+    {
+        matched = true;
+        let key = entry.signer;
+        if is_trusted(config, registry, &key, scope, slot)?
+            || history.is_some_and(|history| history.in_grace(&key, scope, slot))
+        {
+            return Ok(key);
+        }
+    }
+    require!(matched, ErrorCode::SigVerificationFailed);
+    Err(ErrorCode::SignerNotAuthorized.into())
+}
+
+/// Check the co-signing policy for an action worth `amount`: above
+/// `Config.cosign_threshold`, `msg` must be signed by at least
+/// `Config.cosign_required` distinct trusted keys. Retired signers in their
+/// grace window do not count. Claims pass the amount they pay; global
+/// publications pass `None` and always need the full count, whatever the
+/// threshold.
+pub fn verify_cosigners(
+    signatures: &[VerifiedSignature],
+    config: &Config,
+    registry: Option<&SignerRegistry>,
+    scope: u8,
+    msg: &[u8],
+    amount: Option<u64>,
+) -> Result<()> {
+    if config.cosign_required <= 1 {
+        return Ok(());
+    }
+    if let Some(amount) = amount {
+        if amount <= config.cosign_threshold {
+            return Ok(());
+        }
+    }
+
+    let slot = Clock::get()?.slot;
+    let mut signers: Vec<TrustedSigner> = Vec::new();
+    for entry in signatures {
+        let key = entry.signer;
+        let trusted = is_trusted(config, registry, &key, scope, slot)?;
+        if trusted && entry.message == msg && !signers.contains(&key) {
+            signers.push(key);
+        }
+    }
+    require!(
+        signers.len() >= usize::from(config.cosign_required),
+        ErrorCode::InsufficientSignatures
+    );
+    Ok(())
+}
+
+/// Check that enough keys can sign for each of `COSIGNED_SCOPES` to meet
+/// `Config.cosign_required`, so a policy change or a registry edit cannot
+/// leave cosigned actions impossible to authorize.
+pub fn check_cosign_policy(config: &Config, registry: Option<&SignerRegistry>) -> Result<()> {
+    if config.cosign_required <= 1 {
+        return Ok(());
+    }
+    // Without the registry only the config signer can sign.
+    require!(
+        config.signer_registry_active,
+        ErrorCode::InvalidCosignPolicy
+    );
+    let registry = registry.ok_or(ErrorCode::SignerRegistryRequired)?;
+    let slot = Clock::get()?.slot;
+    for scope in COSIGNED_SCOPES {
+        require!(
+            usize::from(config.cosign_required) <= registry.usable_for(scope, slot),
+            ErrorCode::InvalidCosignPolicy
+        );
+    }
+    Ok(())
+}
+
+/// Whether `key` may authorize `scope` at `slot`: `Config.signer` until the
+/// registry is in use, then only registry entries valid for `scope`.
+fn is_trusted(
+    config: &Config,
+    registry: Option<&SignerRegistry>,
+    key: &TrustedSigner,
+    scope: u8,
+    slot: u64,
+) -> Result<bool> {
+    if !config.signer_registry_active {
+        return Ok(*key == config.signer);
+    }
+    let registry = registry.ok_or(ErrorCode::SignerRegistryRequired)?;
+    Ok(registry.is_authorized(key, scope, slot))
+}
@@ -0,0 +1,53 @@
+use crate::errors::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::Mint;
+
+/// Amounts of a reward transfer out of a vault: what leaves the vault and
+/// what reaches the receiver once the mint's transfer fee is withheld.
+pub struct RewardTransfer {
+    pub sent: u64,
+    pub received: u64,
+}
+
+/// Work out the transfer paying `reward` with `mint`. With `gross_up` the
+/// transfer fee is added on top so that `reward` arrives in full; otherwise
+/// `reward` is sent and the fee comes out of it. Mints without the
+/// transfer-fee extension, including every legacy SPL Token mint, take no fee.
+pub fn reward_transfer(
+    mint: &AccountInfo,
+    reward: u64,
+    gross_up: bool,
+    epoch: u64,
+) -> Result<RewardTransfer> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    let Ok(fee_config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(RewardTransfer {
+            sent: reward,
+            received: reward,
+        });
+    };
+
+    if gross_up {
+        let fee = fee_config
+            .calculate_inverse_epoch_fee(epoch, reward)
+            .ok_or(ErrorCode::TransferFeeError)?;
+        let sent = reward.checked_add(fee).ok_or(ErrorCode::TransferFeeError)?;
+        Ok(RewardTransfer {
+            sent,
+            received: reward,
+        })
+    } else {
+        let fee = fee_config
+            .calculate_epoch_fee(epoch, reward)
+            .ok_or(ErrorCode::TransferFeeError)?;
+        Ok(RewardTransfer {
+            sent: reward,
+            received: reward - fee,
+        })
+    }
+}
@@ -4,6 +4,8 @@ import { ConsensusOnchain } from "../target/types/consensus_onchain";
 import { BN } from 'bn.js';
 import {Keypair, PublicKey} from "@solana/web3.js";
 import {assert, expect} from "chai";
+import {createHash} from "crypto";
+import * as fs from "fs";
 import * as ed from '@noble/ed25519';
 import * as bs58 from "bs58";
 import {
@@ -12,7 +14,9 @@ import {
     mintTo,
     getAssociatedTokenAddress,
     TOKEN_PROGRAM_ID, getMint,
-    createAssociatedTokenAccountInstruction, ASSOCIATED_TOKEN_PROGRAM_ID
+    createAssociatedTokenAccountInstruction, ASSOCIATED_TOKEN_PROGRAM_ID,
+    TOKEN_2022_PROGRAM_ID, ExtensionType, getMintLen,
+    createInitializeTransferFeeConfigInstruction, createInitializeMintInstruction
 } from "@solana/spl-token";
 
 describe("consensus_onchain", () => {
@@ -42,6 +46,71 @@ describe("consensus_onchain", () => {
       ],
       program.programId
   )
+  // Domain header every signed payload must carry.
+  const KIND_UPLOAD = 1;
+  const KIND_BADGE = 2;
+  const KIND_CLAIM = 3;
+  const KIND_GLOBAL = 5;
+  // Deployment tag passed to `initialize`; 0 is not a valid tag.
+  const DEPLOYMENT = 1;
+  const domain = (kind: number) => ({
+      'program_id': Array.from(program.programId.toBytes()),
+      'kind': kind,
+      'deployment': DEPLOYMENT,
+  })
+  // Signed payloads stay valid for ten minutes.
+  const expiresAt = () => Math.floor(Date.now() / 1000) + 600;
+  const domainBytes = (kind: number) => Buffer.concat([
+      program.programId.toBuffer(),
+      Buffer.from([kind]),
+      new BN(DEPLOYMENT).toArrayLike(Buffer, 'le', 8),
+  ])
+  const rewardMintPDA = (mint: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from("reward_mint"), mint.toBuffer()],
+      program.programId
+  )[0]
+  const profilePDA = (user: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), user.toBuffer()],
+      program.programId
+  )[0]
+  const [uploadWindowPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("upload_window")],
+      program.programId
+  )
+  // Uploads are normalized to UTC days; `uploadDay(n)` is n days back and
+  // every test uploads for its own day.
+  const DAY = 86400
+  const uploadDay = (n: number) => (Math.floor(Date.now() / 1000 / DAY) - n) * DAY
+  const consensusSeeds = (timestamp: number) => [
+      Buffer.from("consensus"),
+      new BN(Math.floor(timestamp / DAY)).toArrayLike(Buffer, 'le', 8),
+  ]
+  // Addresses under the seeds used before they were namespaced; badges and
+  // claims refuse to run while they still hold an unmigrated account.
+  const legacyBadgePDA = (quiz: number, user: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from(quiz.toString()), user.toBuffer()],
+      program.programId
+  )[0]
+  const legacyRewardPDA = (task: number, user: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from('reward'), Buffer.from(task.toString()), user.toBuffer()],
+      program.programId
+  )[0]
+  const [signerHistoryPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("signer_history")],
+      program.programId
+  )
+  const [programDataPDA] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      anchor.web3.BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+  )
+  // Trusted signers are either Ed25519 keys or Ethereum addresses.
+  const ed25519Signer = (key: PublicKey) => ({ed25519: {0: key}})
+  const SCOPE_ALL = 0b10111
+  const [signerRegistryPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("signers")],
+      program.programId
+  )
+  const ethereumSigner = (address: Buffer) => ({ethereum: {0: Array.from(address)}})
 
     const sendTx = async (ixs :anchor.web3.TransactionInstruction[], keypair: Keypair) => {
        let tx = new anchor.web3.Transaction()
@@ -72,22 +141,86 @@ describe("consensus_onchain", () => {
     await requestAirdrop(admin_keypair);
 
     let fee = new BN('30000')
-    const tx = await program.methods.initialize(admin_keypair.publicKey, fee).accounts({
+    // Only the upgrade authority may initialize.
+    try {
+      await program.methods.initialize(ed25519Signer(user_keypair.publicKey), fee, new BN(DEPLOYMENT)).accounts({
+        config: configPDA,
+        payer: user_keypair.publicKey,
+        program: program.programId,
+        programData: programDataPDA,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      }).signers([user_keypair]).rpc();
+      assert.fail("Should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.eq("OnlyUpgradeAuthority");
+    }
+    // Every deployment must carry its own tag.
+    try {
+      await program.methods.initialize(ed25519Signer(admin_keypair.publicKey), fee, new BN(0)).accounts({
+        config: configPDA,
+        payer: pg.wallet.publicKey,
+        program: program.programId,
+        programData: programDataPDA,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      }).signers([]).rpc();
+      assert.fail("Should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.eq("InvalidDeployment");
+    }
+    const tx = await program.methods.initialize(ed25519Signer(admin_keypair.publicKey), fee, new BN(DEPLOYMENT)).accounts({
       config: configPDA,
       payer: pg.wallet.publicKey,
+      program: program.programId,
+      programData: programDataPDA,
       systemProgram: anchor.web3.SystemProgram.programId,
     }).signers([]).rpc();
     console.log("Your transaction signature", tx);
     assert((await program.account.config.fetch(configPDA)).fee.eq(
         fee)
     )
-    assert((await program.account.config.fetch(configPDA)).signer.equals(admin_keypair.publicKey))
+    assert((await program.account.config.fetch(configPDA)).signer.ed25519[0].equals(admin_keypair.publicKey))
+    assert((await program.account.config.fetch(configPDA)).version == 1)
+
+    // A migrated Config must be bound to a deployment.
+    try {
+      await program.methods.migrateConfig(new BN(0)).accounts({
+        config: configPDA,
+        payer: pg.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      }).signers([]).rpc();
+      assert.fail("Should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.eq("InvalidDeployment");
+    }
+
+    // Accounts created in the current layout have nothing to migrate.
+    try {
+      await program.methods.migrateConfig(new BN(1)).accounts({
+        config: configPDA,
+        payer: pg.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      }).signers([]).rpc();
+      assert.fail("Should have failed");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.eq("AccountAlreadyMigrated");
+    }
+
+    // Keep the default UTC day, but accept uploads up to a month back.
+    await program.methods.setUploadWindow(new BN(DAY), new BN(30 * DAY), new BN(300)).accounts({
+      config: configPDA,
+      uploadWindow: uploadWindowPDA,
+      payer: pg.wallet.publicKey,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    }).signers([]).rpc();
+    assert((await program.account.uploadWindow.fetch(uploadWindowPDA)).maxPast.eq(new BN(30 * DAY)))
   });
 
   it("User upload consensus data", async () => {
     const msgJson = {
+      'domain': domain(KIND_UPLOAD),
+      'expires_at': expiresAt(),
       'consensus_proof':'9b64d63367328fd980b6e88af0dc46c437bf2c3906a9b000eccd66a6e4599938',
-        'timestamp':12345,
+        'timestamp': uploadDay(1),
     }
     const message = Uint8Array.from(
         Buffer.from(JSON.stringify(msgJson))
@@ -95,18 +228,22 @@ describe("consensus_onchain", () => {
     const signature = await ed.sign(message, admin_keypair.secretKey.slice(0, 32));
 
     const [userPDA] = PublicKey.findProgramAddressSync(
-        [Buffer.from('12345'), user_keypair.publicKey.toBuffer()],
+        [...consensusSeeds(uploadDay(1)), user_keypair.publicKey.toBuffer()],
         program.programId,
     );
 
     let uploadValidationInstruction = await program.methods.uploadValidation(
-        new BN('12345'),
+        new BN(uploadDay(1)),
         Buffer.from(message),
         Array.from(signature),
     ).accounts({
       user:user_keypair.publicKey,
       config: configPDA,
       consensus:userPDA,
+      uploadWindow: uploadWindowPDA,
+      profile: profilePDA(user_keypair.publicKey),
+      signerRegistry: null,
+      signerHistory: null,
       ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
       systemProgram: anchor.web3.SystemProgram.programId,
     }).instruction()
@@ -143,19 +280,26 @@ describe("consensus_onchain", () => {
     )
     assert.isFalse((await program.account.consensusState.fetch(userPDA)).global
     )
+    const profile = await program.account.contributorProfile.fetch(profilePDA(user_keypair.publicKey))
+    assert(profile.totalUploads.eq(new BN(1)))
+    assert(profile.firstUpload.eq(new BN(uploadDay(1))))
+    expect(profile.currentStreak).to.eq(1)
 
     const [adminPDA] = PublicKey.findProgramAddressSync(
-        [Buffer.from('12345'), admin_keypair.publicKey.toBuffer()],
+        [...consensusSeeds(uploadDay(1)), admin_keypair.publicKey.toBuffer()],
         program.programId,
     );
     uploadValidationInstruction = await program.methods.uploadValidation(
-        new BN('12345'),
+        new BN(uploadDay(1)),
         Buffer.from(message),
         Array.from(signature),
     ).accounts({
       user:admin_keypair.publicKey,
       config: configPDA,
       consensus:adminPDA,
+      uploadWindow: uploadWindowPDA,
+      signerRegistry: null,
+      signerHistory: null,
       ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
       systemProgram: anchor.web3.SystemProgram.programId,
     }).instruction()
@@ -187,16 +331,230 @@ describe("consensus_onchain", () => {
           `Should not have failed with the following error:\n${error.msg}`
       );
     }
-    assert.isTrue((await program.account.consensusState.fetch(adminPDA)).global
+    // Global rollups live in GlobalConsensus; uploads no longer set the flag,
+    // even when the trusted signer uploads.
+    assert.isFalse((await program.account.consensusState.fetch(adminPDA)).global
     )
   });
 
+    it("Upload window", async () => {
+        const proof = Buffer.from('9b64d63367328fd980b6e88af0dc46c437bf2c3906a9b000eccd66a6e4599938', 'hex');
+        const uploadIx = async (timestamp: number) => {
+            const message = Uint8Array.from(Buffer.concat([
+                Buffer.from([1]),
+                domainBytes(KIND_UPLOAD),
+                new BN(expiresAt()).toArrayLike(Buffer, 'le', 8),
+                new BN(timestamp).toArrayLike(Buffer, 'le', 8),
+                proof,
+            ]));
+            const signature = await ed.sign(message, admin_keypair.secretKey.slice(0, 32));
+            const [consensusPDA] = PublicKey.findProgramAddressSync(
+                [...consensusSeeds(timestamp - timestamp % DAY), user_keypair.publicKey.toBuffer()],
+                program.programId,
+            );
+            return [
+                anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+                    publicKey: admin_keypair.publicKey.toBytes(),
+                    message: message,
+                    signature: signature,
+                }),
+                await program.methods.uploadValidation(
+                    new BN(timestamp),
+                    Buffer.from(message),
+                    Array.from(signature),
+                ).accounts({
+                    user:user_keypair.publicKey,
+                    config: configPDA,
+                    uploadWindow: uploadWindowPDA,
+                    consensus:consensusPDA,
+                    signerRegistry: null,
+                    signerHistory: null,
+                    ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+                    systemProgram: anchor.web3.SystemProgram.programId,
+                }).instruction(),
+            ];
+        };
+
+        // A second upload later in the same day derives the same account.
+        try {
+            const tx = new anchor.web3.Transaction().add(...await uploadIx(uploadDay(1) + 3600));
+            await anchor.web3.sendAndConfirmTransaction(pg.connection, tx, [user_keypair]);
+            assert.fail("Should have failed");
+        } catch (error) {
+            assert(error.logs.some((log: string) => log.includes("already in use")))
+        }
+
+        // Future-dated and too old uploads are rejected.
+        for (const timestamp of [uploadDay(-1) + 3600, uploadDay(31)]) {
+            try {
+                const tx = new anchor.web3.Transaction().add(...await uploadIx(timestamp));
+                await anchor.web3.sendAndConfirmTransaction(pg.connection, tx, [user_keypair]);
+                assert.fail("Should have failed");
+            } catch (error) {
+                assert(error.logs.some((log: string) => log.includes("TimestampOutOfWindow")))
+            }
+        }
+    });
+
+    it("User upload binary consensus data", async () => {
+        const proof = Buffer.from('9b64d63367328fd980b6e88af0dc46c437bf2c3906a9b000eccd66a6e4599938', 'hex');
+        // PAYLOAD_BORSH_V1 | domain | expires_at: i64 | timestamp: u64 | consensus_proof: [u8; 32]
+        const message = Uint8Array.from(Buffer.concat([
+            Buffer.from([1]),
+            domainBytes(KIND_UPLOAD),
+            new BN(expiresAt()).toArrayLike(Buffer, 'le', 8),
+            new BN(uploadDay(2)).toArrayLike(Buffer, 'le', 8),
+            proof,
+        ]));
+        const signature = await ed.sign(message, admin_keypair.secretKey.slice(0, 32));
+
+        const [userPDA] = PublicKey.findProgramAddressSync(
+            [...consensusSeeds(uploadDay(2)), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        const uploadValidationInstruction = await program.methods.uploadValidation(
+            new BN(uploadDay(2)),
+            Buffer.from(message),
+            Array.from(signature),
+        ).accounts({
+            user:user_keypair.publicKey,
+            config: configPDA,
+            consensus:userPDA,
+            uploadWindow: uploadWindowPDA,
+            signerRegistry: null,
+            signerHistory: null,
+            ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).instruction()
+        const sigIx = anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: admin_keypair.publicKey.toBytes(),
+            message: message,
+            signature: signature,
+        });
+        await sendTx([sigIx, uploadValidationInstruction], user_keypair);
+        const consensus = await program.account.consensusState.fetch(userPDA);
+        assert(consensus.timestamp.eq(new BN(uploadDay(2))))
+        assert(Buffer.from(consensus.consensusProof).equals(proof))
+
+        // The same payload signed for another instruction is rejected.
+        const badgeMessage = Uint8Array.from(Buffer.concat([
+            Buffer.from([1]),
+            domainBytes(KIND_BADGE),
+            new BN(expiresAt()).toArrayLike(Buffer, 'le', 8),
+            new BN(uploadDay(3)).toArrayLike(Buffer, 'le', 8),
+            proof,
+        ]));
+        const badgeSignature = await ed.sign(badgeMessage, admin_keypair.secretKey.slice(0, 32));
+        const [otherPDA] = PublicKey.findProgramAddressSync(
+            [...consensusSeeds(uploadDay(3)), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        const wrongDomainInstruction = await program.methods.uploadValidation(
+            new BN(uploadDay(3)),
+            Buffer.from(badgeMessage),
+            Array.from(badgeSignature),
+        ).accounts({
+            user:user_keypair.publicKey,
+            config: configPDA,
+            consensus:otherPDA,
+            uploadWindow: uploadWindowPDA,
+            signerRegistry: null,
+            signerHistory: null,
+            ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).instruction()
+        const tx = new anchor.web3.Transaction()
+            .add(anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+                publicKey: admin_keypair.publicKey.toBytes(),
+                message: badgeMessage,
+                signature: badgeSignature,
+            }))
+            .add(wrongDomainInstruction);
+        try {
+            await anchor.web3.sendAndConfirmTransaction(pg.connection, tx, [user_keypair]);
+            assert.fail("Should have failed");
+        } catch (error) {
+            assert(error.logs.some((log: string) => log.includes("DomainKindMismatch")))
+        }
+
+        // Expired payloads are rejected.
+        const expiredMessage = Uint8Array.from(Buffer.concat([
+            Buffer.from([1]),
+            domainBytes(KIND_UPLOAD),
+            new BN(Math.floor(Date.now() / 1000) - 60).toArrayLike(Buffer, 'le', 8),
+            new BN(uploadDay(3)).toArrayLike(Buffer, 'le', 8),
+            proof,
+        ]));
+        const expiredSignature = await ed.sign(expiredMessage, admin_keypair.secretKey.slice(0, 32));
+        const expiredInstruction = await program.methods.uploadValidation(
+            new BN(uploadDay(3)),
+            Buffer.from(expiredMessage),
+            Array.from(expiredSignature),
+        ).accounts({
+            user:user_keypair.publicKey,
+            config: configPDA,
+            consensus:otherPDA,
+            uploadWindow: uploadWindowPDA,
+            signerRegistry: null,
+            signerHistory: null,
+            ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).instruction()
+        const expiredTx = new anchor.web3.Transaction()
+            .add(anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+                publicKey: admin_keypair.publicKey.toBytes(),
+                message: expiredMessage,
+                signature: expiredSignature,
+            }))
+            .add(expiredInstruction);
+        try {
+            await anchor.web3.sendAndConfirmTransaction(pg.connection, expiredTx, [user_keypair]);
+            assert.fail("Should have failed");
+        } catch (error) {
+            assert(error.logs.some((log: string) => log.includes("MessageExpired")))
+        }
+
+        // The PDA seed argument must match the signed timestamp.
+        const [mismatchPDA] = PublicKey.findProgramAddressSync(
+            [...consensusSeeds(uploadDay(4)), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        const mismatchInstruction = await program.methods.uploadValidation(
+            new BN(uploadDay(4)),
+            Buffer.from(message),
+            Array.from(signature),
+        ).accounts({
+            user:user_keypair.publicKey,
+            config: configPDA,
+            consensus:mismatchPDA,
+            uploadWindow: uploadWindowPDA,
+            signerRegistry: null,
+            signerHistory: null,
+            ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).instruction()
+        const mismatchTx = new anchor.web3.Transaction()
+            .add(anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+                publicKey: admin_keypair.publicKey.toBytes(),
+                message: message,
+                signature: signature,
+            }))
+            .add(mismatchInstruction);
+        try {
+            await anchor.web3.sendAndConfirmTransaction(pg.connection, mismatchTx, [user_keypair]);
+            assert.fail("Should have failed");
+        } catch (error) {
+            assert(error.logs.some((log: string) => log.includes("TimestampMismatch")))
+        }
+    });
+
     it("Is updated!", async () => {
         const other_admin_keypair = Keypair.generate();
         await requestAirdrop(other_admin_keypair);
 
-        const tx = await program.methods.update(other_admin_keypair.publicKey, new BN('66')).accounts({
+        const tx = await program.methods.update(ed25519Signer(other_admin_keypair.publicKey), new BN('66'), new BN(1000), new BN(3600)).accounts({
             config: configPDA,
+            signerHistory: signerHistoryPDA,
             payer: pg.wallet.publicKey,
             systemProgram: anchor.web3.SystemProgram.programId,
         }).signers([]).rpc();
@@ -204,11 +562,13 @@ describe("consensus_onchain", () => {
         assert((await program.account.config.fetch(configPDA)).fee.eq(
             new BN('66'))
         )
-        assert((await program.account.config.fetch(configPDA)).signer.equals(other_admin_keypair.publicKey))
+        assert((await program.account.config.fetch(configPDA)).signer.ed25519[0].equals(other_admin_keypair.publicKey))
 
         const msgJson = {
+            'domain': domain(KIND_UPLOAD),
+            'expires_at': expiresAt(),
             'consensus_proof':'9b64d63367328fd980b6e88af0dc46c437bf2c3906a9b000eccd66a6e4599938',
-            'timestamp':12345,
+            'timestamp': uploadDay(5),
         }
         const message = Uint8Array.from(
             Buffer.from(JSON.stringify(msgJson))
@@ -216,18 +576,21 @@ describe("consensus_onchain", () => {
         const signature = await ed.sign(message, other_admin_keypair.secretKey.slice(0, 32));
 
         const [userPDA] = PublicKey.findProgramAddressSync(
-            [Buffer.from('123456'), user_keypair.publicKey.toBuffer()],
+            [...consensusSeeds(uploadDay(5)), user_keypair.publicKey.toBuffer()],
             program.programId,
         );
 
         let uploadValidationInstruction = await program.methods.uploadValidation(
-            new BN('123456'),
+            new BN(uploadDay(5)),
             Buffer.from(message),
             Array.from(signature),
         ).accounts({
             user:user_keypair.publicKey,
             config: configPDA,
             consensus:userPDA,
+            uploadWindow: uploadWindowPDA,
+            signerRegistry: null,
+            signerHistory: null,
             ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
             systemProgram: anchor.web3.SystemProgram.programId,
         }).instruction()
@@ -264,11 +627,48 @@ describe("consensus_onchain", () => {
         )
         assert.isFalse((await program.account.consensusState.fetch(userPDA)).global
         )
+
+        // The retired signer is still accepted during the grace window,
+        // which is fixed when it is retired.
+        const retired = (await program.account.signerHistory.fetch(signerHistoryPDA)).signers[0];
+        assert(retired.graceUntil.eq(retired.retiredAt.add(new BN(1000))))
+        expect(retired.scope).to.eq(SCOPE_ALL)
+        const history = await program.account.signerHistory.fetch(signerHistoryPDA);
+        assert(history.signers[history.signers.length - 1].key.ed25519[0].equals(admin_keypair.publicKey))
+        const graceMessage = Uint8Array.from(
+            Buffer.from(JSON.stringify({...msgJson, 'timestamp': uploadDay(6)}))
+        );
+        const oldSignature = await ed.sign(graceMessage, admin_keypair.secretKey.slice(0, 32));
+        const [gracePDA] = PublicKey.findProgramAddressSync(
+            [...consensusSeeds(uploadDay(6)), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        uploadValidationInstruction = await program.methods.uploadValidation(
+            new BN(uploadDay(6)),
+            Buffer.from(graceMessage),
+            Array.from(oldSignature),
+        ).accounts({
+            user:user_keypair.publicKey,
+            config: configPDA,
+            consensus:gracePDA,
+            uploadWindow: uploadWindowPDA,
+            signerRegistry: null,
+            signerHistory: signerHistoryPDA,
+            ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).instruction()
+        const sigIx = anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: admin_keypair.publicKey.toBytes(),
+            message: graceMessage,
+            signature: oldSignature,
+        });
+        await sendTx([sigIx, uploadValidationInstruction], user_keypair);
     });
 
     it("Signer is owner", async () => {
-        const tx = await program.methods.update(pg.wallet.publicKey, new BN('77')).accounts({
+        const tx = await program.methods.update(ed25519Signer(pg.wallet.publicKey), new BN('77'), new BN(0), new BN(3600)).accounts({
             config: configPDA,
+            signerHistory: signerHistoryPDA,
             payer: pg.wallet.publicKey,
             systemProgram: anchor.web3.SystemProgram.programId,
         }).signers([]).rpc();
@@ -276,11 +676,13 @@ describe("consensus_onchain", () => {
         assert((await program.account.config.fetch(configPDA)).fee.eq(
             new BN('77'))
         )
-        assert((await program.account.config.fetch(configPDA)).signer.equals(pg.wallet.publicKey))
+        assert((await program.account.config.fetch(configPDA)).signer.ed25519[0].equals(pg.wallet.publicKey))
 
         const msgJson = {
+            'domain': domain(KIND_UPLOAD),
+            'expires_at': expiresAt(),
             'consensus_proof':'9b64d63367328fd980b6e88af0dc46c437bf2c3906a9b000eccd66a6e4599938',
-            'timestamp':1234567,
+            'timestamp': uploadDay(7),
         }
         const message = Uint8Array.from(
             Buffer.from(JSON.stringify(msgJson))
@@ -288,18 +690,21 @@ describe("consensus_onchain", () => {
         const signature = await ed.sign(message, pg.wallet.payer.secretKey.slice(0, 32));
 
         const [userPDA] = PublicKey.findProgramAddressSync(
-            [Buffer.from('1234567'), user_keypair.publicKey.toBuffer()],
+            [...consensusSeeds(uploadDay(7)), user_keypair.publicKey.toBuffer()],
             program.programId,
         );
 
         let uploadValidationInstruction = await program.methods.uploadValidation(
-            new BN('1234567'),
+            new BN(uploadDay(7)),
             Buffer.from(message),
             Array.from(signature),
         ).accounts({
             user:user_keypair.publicKey,
             config: configPDA,
             consensus:userPDA,
+            uploadWindow: uploadWindowPDA,
+            signerRegistry: null,
+            signerHistory: null,
             ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
             systemProgram: anchor.web3.SystemProgram.programId,
         }).instruction()
@@ -342,14 +747,17 @@ describe("consensus_onchain", () => {
         const oldBalance = await pg.connection.getBalance(
             configPDA
         );
-    await program.methods.update(pg.wallet.publicKey, new BN('2000000')).accounts({
+    await program.methods.update(ed25519Signer(pg.wallet.publicKey), new BN('2000000'), new BN(0), new BN(3600)).accounts({
         config: configPDA,
+        signerHistory: signerHistoryPDA,
         payer: pg.wallet.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
     }).signers([]).rpc();
       const msgJson = {
+        'domain': domain(KIND_UPLOAD),
+        'expires_at': expiresAt(),
         'consensus_proof':'9b64d63367328fd980b6e88af0dc46c437bf2c3906a9b000eccd66a6e4599938',
-          'timestamp':123452,
+          'timestamp': uploadDay(8),
       }
       const message = Uint8Array.from(
           Buffer.from(JSON.stringify(msgJson))
@@ -357,18 +765,21 @@ describe("consensus_onchain", () => {
       const signature = await ed.sign(message, pg.wallet.payer.secretKey.slice(0, 32));
 
       const [userPDA] = PublicKey.findProgramAddressSync(
-          [Buffer.from('123452'), user_keypair.publicKey.toBuffer()],
+          [...consensusSeeds(uploadDay(8)), user_keypair.publicKey.toBuffer()],
           program.programId,
       );
 
       let uploadValidationInstruction = await program.methods.uploadValidation(
-          new BN('123452'),
+          new BN(uploadDay(8)),
           Buffer.from(message),
           Array.from(signature),
       ).accounts({
         user:user_keypair.publicKey,
         config: configPDA,
         consensus:userPDA,
+        uploadWindow: uploadWindowPDA,
+        signerRegistry: null,
+        signerHistory: null,
         ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
         systemProgram: anchor.web3.SystemProgram.programId,
       }).instruction()
@@ -405,15 +816,56 @@ describe("consensus_onchain", () => {
         );
         assert((newBalance-oldBalance)==2000000)
 
-        await program.methods.withdraw(new BN(1000000)).accounts({
+        const withdrawTx = await program.methods.withdraw(new BN(1000000)).accounts({
             config: configPDA,
+            destination: pg.wallet.publicKey,
             payer: pg.wallet.publicKey,
             systemProgram: anchor.web3.SystemProgram.programId,
-        }).signers([]).rpc();
+        }).signers([]).rpc({commitment: "confirmed"});
        let otherNewBalance = await pg.connection.getBalance(
             configPDA
         );
         assert((newBalance-otherNewBalance)==1000000)
+
+        const withdrawTxInfo = await pg.connection.getTransaction(withdrawTx, {
+            commitment: "confirmed",
+            maxSupportedTransactionVersion: 0,
+        });
+        const eventParser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+        const events = [...eventParser.parseLogs(withdrawTxInfo.meta.logMessages)];
+        expect(events.length).to.eq(1);
+        expect(events[0].name.toLowerCase()).to.eq("feeswithdrawn");
+        assert(events[0].data.amount.eq(new BN(1000000)))
+        assert(events[0].data.to.equals(pg.wallet.publicKey))
+        const config = await program.account.config.fetch(configPDA);
+        assert(config.totalWithdrawn.eq(events[0].data.totalWithdrawn))
+        assert(config.feesCollected.gte(new BN(2000000)))
+
+        // Withdrawing everything leaves Config rent-exempt, and any account can receive the fees.
+        const treasury = Keypair.generate();
+        await program.methods.withdraw(new BN(0)).accounts({
+            config: configPDA,
+            destination: treasury.publicKey,
+            payer: pg.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([]).rpc({commitment: "confirmed"});
+        const configInfo = await pg.connection.getAccountInfo(configPDA);
+        const rentExempt = await pg.connection.getMinimumBalanceForRentExemption(configInfo.data.length);
+        assert(configInfo.lamports == rentExempt)
+        assert((await pg.connection.getBalance(treasury.publicKey)) == otherNewBalance - rentExempt)
+
+        // Withdrawing to Config itself would only inflate totalWithdrawn.
+        try {
+            await program.methods.withdraw(new BN(0)).accounts({
+                config: configPDA,
+                destination: configPDA,
+                payer: pg.wallet.publicKey,
+                systemProgram: anchor.web3.SystemProgram.programId,
+            }).signers([]).rpc();
+            assert.fail("Should have failed");
+        } catch (error) {
+            expect(error.error.errorCode.code).to.eq("InvalidWithdrawDestination");
+        }
     });
 
     it("User upload badge data", async () => {
@@ -424,6 +876,8 @@ describe("consensus_onchain", () => {
         let quiz = 9;
         let tier = 1;
         let msgJson = {
+            'domain': domain(KIND_BADGE),
+            'expires_at': expiresAt(),
             'quiz':quiz,
             'tier':tier,
             'owner': Array.from(user_keypair.publicKey.toBytes()),
@@ -437,7 +891,7 @@ describe("consensus_onchain", () => {
             program.programId,
         );
         let [badgePDA] = PublicKey.findProgramAddressSync(
-            [Buffer.from(quiz.toString()), user_keypair.publicKey.toBuffer()],
+            [Buffer.from('badge'), new BN(quiz).toArrayLike(Buffer, 'le', 8), user_keypair.publicKey.toBuffer()],
             program.programId,
         );
 
@@ -451,6 +905,10 @@ describe("consensus_onchain", () => {
             badgeConfigPool: badgeConfigPoolPDA,
             badgeConfig: badgeConfigPDA,
             badge:badgePDA,
+            legacyBadge: legacyBadgePDA(quiz, user_keypair.publicKey),
+            profile: profilePDA(user_keypair.publicKey),
+            signerRegistry: null,
+            signerHistory: null,
             ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
             systemProgram: anchor.web3.SystemProgram.programId,
         }).instruction()
@@ -488,6 +946,7 @@ describe("consensus_onchain", () => {
         assert((await program.account.badge.fetch(badgePDA)).quiz.eq(new BN(quiz)))
         assert((await program.account.badge.fetch(badgePDA)).tier.eq(new BN(tier)))
         assert((await program.account.badge.fetch(badgePDA)).owner = user_keypair.publicKey)
+        assert((await program.account.contributorProfile.fetch(profilePDA(user_keypair.publicKey))).badges.eq(new BN(1)))
         // Should error
         try {
             await anchor.web3.sendAndConfirmTransaction(
@@ -502,6 +961,8 @@ describe("consensus_onchain", () => {
         quiz = 2;
         tier = 1;
         msgJson = {
+            'domain': domain(KIND_BADGE),
+            'expires_at': expiresAt(),
             'quiz':quiz,
             'tier':tier,
             'owner': Array.from(user_keypair.publicKey.toBytes()),
@@ -515,7 +976,7 @@ describe("consensus_onchain", () => {
             program.programId,
         );
         [badgePDA] = PublicKey.findProgramAddressSync(
-            [Buffer.from(quiz.toString()), user_keypair.publicKey.toBuffer()],
+            [Buffer.from('badge'), new BN(quiz).toArrayLike(Buffer, 'le', 8), user_keypair.publicKey.toBuffer()],
             program.programId,
         );
 
@@ -529,6 +990,10 @@ describe("consensus_onchain", () => {
             badgeConfigPool: badgeConfigPoolPDA,
             badgeConfig: badgeConfigPDA,
             badge:badgePDA,
+            legacyBadge: legacyBadgePDA(quiz, user_keypair.publicKey),
+            profile: profilePDA(user_keypair.publicKey),
+            signerRegistry: null,
+            signerHistory: null,
             ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
             systemProgram: anchor.web3.SystemProgram.programId,
         }).instruction()
@@ -569,6 +1034,8 @@ describe("consensus_onchain", () => {
         const other_user_keypair = Keypair.generate();
         await requestAirdrop(other_user_keypair);
         msgJson = {
+            'domain': domain(KIND_BADGE),
+            'expires_at': expiresAt(),
             'quiz':quiz,
             'tier':tier,
             'owner': Array.from(other_user_keypair.publicKey.toBytes()),
@@ -582,7 +1049,7 @@ describe("consensus_onchain", () => {
             program.programId,
         );
         [badgePDA] = PublicKey.findProgramAddressSync(
-            [Buffer.from(quiz.toString()), other_user_keypair.publicKey.toBuffer()],
+            [Buffer.from('badge'), new BN(quiz).toArrayLike(Buffer, 'le', 8), other_user_keypair.publicKey.toBuffer()],
             program.programId,
         );
 
@@ -596,6 +1063,10 @@ describe("consensus_onchain", () => {
             badgeConfigPool: badgeConfigPoolPDA,
             badgeConfig: badgeConfigPDA,
             badge:badgePDA,
+            legacyBadge: legacyBadgePDA(quiz, other_user_keypair.publicKey),
+            profile: profilePDA(user_keypair.publicKey),
+            signerRegistry: null,
+            signerHistory: null,
             ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
             systemProgram: anchor.web3.SystemProgram.programId,
         }).instruction()
@@ -676,11 +1147,14 @@ describe("consensus_onchain", () => {
         const token_vault_ata_balance = await pg.connection.getTokenAccountBalance(token_vault_ata);
         expect(token_vault_ata_balance.value.amount).to.eq(mintAmount.toString());
 
-        let initClaimTx = await program.methods.iniClaim(
+        let initClaimTx = await program.methods.registerRewardMint(
+            true,
+            false
         ).accounts({
             state:statePda,
             mint:mint,
             tokenVault:token_vault_ata,
+            rewardMint: rewardMintPDA(mint),
             config: configPDA,
             payer:pg.wallet.publicKey,
             systemProgram: anchor.web3.SystemProgram.programId,
@@ -691,18 +1165,36 @@ describe("consensus_onchain", () => {
         let task =1 ;
         let nonce = 0;
         let reward = 1000000;
+        const [limitsPDA] = PublicKey.findProgramAddressSync(
+            [Buffer.from('limits'), mint.toBuffer()],
+            program.programId,
+        );
+        const [usagePDA] = PublicKey.findProgramAddressSync(
+            [Buffer.from('usage'), mint.toBuffer(), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        await program.methods.setMintLimits(new BN(reward), new BN(reward * 2), new BN(0)).accounts({
+            config: configPDA,
+            mint: mint,
+            limits: limitsPDA,
+            payer: pg.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([]).rpc();
         let msgJson = {
+            'domain': domain(KIND_CLAIM),
+            'expires_at': expiresAt(),
             'task':task,
             'nonce':nonce,
             'reward':reward,
             'receiver': Array.from(user_keypair.publicKey.toBytes()),
+            'mint': Array.from(mint.toBytes()),
         }
         let message = Uint8Array.from(
             Buffer.from(JSON.stringify(msgJson))
         );
         let signature = await ed.sign(message, pg.wallet.payer.secretKey.slice(0, 32));
         let [rewardPDA] = PublicKey.findProgramAddressSync(
-            [Buffer.from('reward'), Buffer.from(task.toString()), user_keypair.publicKey.toBuffer()],
+            [Buffer.from('reward'), new BN(task).toArrayLike(Buffer, 'le', 2), mint.toBuffer(), user_keypair.publicKey.toBuffer()],
             program.programId,
         );
         const receiver_ata = await getAssociatedTokenAddress(
@@ -727,9 +1219,16 @@ describe("consensus_onchain", () => {
             config: configPDA,
             state:statePda,
             reward:rewardPDA,
+            legacyReward: legacyRewardPDA(task, user_keypair.publicKey),
+            rewardMint: rewardMintPDA(mint),
             tokenVault:token_vault_ata,
             receiver:receiver_ata,
             mint: mint,
+            limits: limitsPDA,
+            usage: usagePDA,
+            profile: profilePDA(user_keypair.publicKey),
+            signerRegistry: null,
+            signerHistory: null,
             ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
             systemProgram: anchor.web3.SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
@@ -746,5 +1245,965 @@ describe("consensus_onchain", () => {
         let receiver_ata_balance = await pg.connection.getTokenAccountBalance(receiver_ata);
         expect(receiver_ata_balance.value.amount).to.eq(reward.toString());
         assert((await program.account.claimReward.fetch(rewardPDA)).times==1)
+        const buckets = (await program.account.userUsage.fetch(usagePDA)).buckets
+        assert(buckets.reduce((total, amount) => total.add(amount), new BN(0)).eq(new BN(reward)))
+        const claimed = (await program.account.contributorProfile.fetch(profilePDA(user_keypair.publicKey))).claimed
+        assert(claimed[0].mint.equals(mint) && claimed[0].amount.eq(new BN(reward)))
+
+        // Only the config signer can sign claims, so two cosigners could
+        // never be collected.
+        try {
+            await program.methods.setCosignPolicy(new BN(reward - 1), 2).accounts({
+                config: configPDA,
+                signerRegistry: null,
+                payer: pg.wallet.publicKey,
+            }).signers([]).rpc();
+            assert.fail("Should have failed");
+        } catch (error) {
+            expect(error.error.errorCode.code).to.eq("InvalidCosignPolicy");
+        }
+
+        // The owner can sweep tokens out of the vault.
+        const owner_ata = await createAssociatedTokenAccount(
+            pg.connection,
+            pg.wallet.payer,
+            mint,
+            pg.wallet.publicKey
+        );
+        await program.methods.withdrawTokens(new BN(1000)).accounts({
+            config: configPDA,
+            state: statePda,
+            mint: mint,
+            rewardMint: rewardMintPDA(mint),
+            tokenVault: token_vault_ata,
+            destination: owner_ata,
+            payer: pg.wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).signers([]).rpc();
+        const owner_ata_balance = await pg.connection.getTokenAccountBalance(owner_ata);
+        expect(owner_ata_balance.value.amount).to.eq('1000');
+    });
+
+    it("Ownership transfer", async () => {
+        const new_owner_keypair = Keypair.generate();
+        await requestAirdrop(new_owner_keypair);
+
+        // A proposal alone does not move control.
+        await program.methods.proposeOwner(new_owner_keypair.publicKey).accounts({
+            config: configPDA,
+            payer: pg.wallet.publicKey,
+        }).signers([]).rpc();
+        let config = await program.account.config.fetch(configPDA);
+        assert(config.owner.equals(pg.wallet.publicKey))
+        assert(config.pendingOwner.equals(new_owner_keypair.publicKey))
+
+        // Only the pending owner can accept.
+        try {
+            await program.methods.acceptOwner().accounts({
+                config: configPDA,
+                newOwner: user_keypair.publicKey,
+            }).signers([user_keypair]).rpc();
+            assert.fail("Should have failed");
+        } catch (error) {
+            expect(error.error.errorCode.code).to.eq("OnlyPendingOwner");
+        }
+
+        await program.methods.acceptOwner().accounts({
+            config: configPDA,
+            newOwner: new_owner_keypair.publicKey,
+        }).signers([new_owner_keypair]).rpc();
+        config = await program.account.config.fetch(configPDA);
+        assert(config.owner.equals(new_owner_keypair.publicKey))
+        assert(config.pendingOwner.equals(PublicKey.default))
+
+        // Hand control back, cancelling a mistyped proposal on the way.
+        await program.methods.proposeOwner(user_keypair.publicKey).accounts({
+            config: configPDA,
+            payer: new_owner_keypair.publicKey,
+        }).signers([new_owner_keypair]).rpc();
+        await program.methods.cancelOwnerTransfer().accounts({
+            config: configPDA,
+            payer: new_owner_keypair.publicKey,
+        }).signers([new_owner_keypair]).rpc();
+        assert((await program.account.config.fetch(configPDA)).pendingOwner.equals(PublicKey.default))
+
+        await program.methods.proposeOwner(pg.wallet.publicKey).accounts({
+            config: configPDA,
+            payer: new_owner_keypair.publicKey,
+        }).signers([new_owner_keypair]).rpc();
+        await program.methods.acceptOwner().accounts({
+            config: configPDA,
+            newOwner: pg.wallet.publicKey,
+        }).signers([]).rpc();
+        assert((await program.account.config.fetch(configPDA)).owner.equals(pg.wallet.publicKey))
+    });
+
+    it("Signer registry", async () => {
+        const node_keypair = Keypair.generate();
+        // SCOPE_UPLOAD only
+        await program.methods.setSigner(ed25519Signer(node_keypair.publicKey), true, new BN(0), new BN(0), 1).accounts({
+            config: configPDA,
+            signerRegistry: signerRegistryPDA,
+            payer: pg.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([]).rpc();
+        let registry = await program.account.signerRegistry.fetch(signerRegistryPDA);
+        assert(registry.signers.length == 1)
+        assert(registry.signers[0].key.ed25519[0].equals(node_keypair.publicKey))
+        // The registry now replaces the config signer, which stays trusted
+        // only through its own entry.
+        assert.isTrue((await program.account.config.fetch(configPDA)).signerRegistryActive)
+        await program.methods.setSigner(ed25519Signer(pg.wallet.publicKey), true, new BN(0), new BN(0), SCOPE_ALL).accounts({
+            config: configPDA,
+            signerRegistry: signerRegistryPDA,
+            payer: pg.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([]).rpc();
+
+        const msgJson = {
+            'domain': domain(KIND_UPLOAD),
+            'expires_at': expiresAt(),
+            'consensus_proof':'9b64d63367328fd980b6e88af0dc46c437bf2c3906a9b000eccd66a6e4599938',
+            'timestamp': uploadDay(9),
+        }
+        const message = Uint8Array.from(
+            Buffer.from(JSON.stringify(msgJson))
+        );
+        const signature = await ed.sign(message, node_keypair.secretKey.slice(0, 32));
+        const [userPDA] = PublicKey.findProgramAddressSync(
+            [...consensusSeeds(uploadDay(9)), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        const uploadValidationInstruction = await program.methods.uploadValidation(
+            new BN(uploadDay(9)),
+            Buffer.from(message),
+            Array.from(signature),
+        ).accounts({
+            user:user_keypair.publicKey,
+            config: configPDA,
+            consensus:userPDA,
+            uploadWindow: uploadWindowPDA,
+            signerRegistry: signerRegistryPDA,
+            ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).instruction()
+        const sigIx = anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: node_keypair.publicKey.toBytes(),
+            message: message,
+            signature: signature,
+        });
+        // The Ed25519 instruction no longer has to sit right before ours.
+        const computeIx = anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({units: 400_000});
+        await sendTx([sigIx, computeIx, uploadValidationInstruction], user_keypair);
+        assert((await program.account.consensusState.fetch(userPDA)).timestamp.eq(new BN(uploadDay(9))))
+
+        await program.methods.removeSigner(ed25519Signer(node_keypair.publicKey)).accounts({
+            config: configPDA,
+            signerRegistry: signerRegistryPDA,
+            payer: pg.wallet.publicKey,
+        }).signers([]).rpc();
+        registry = await program.account.signerRegistry.fetch(signerRegistryPDA);
+        assert(registry.signers.length == 1)
+
+        // The remaining key is the only one allowed to sign claims; a key
+        // that only becomes valid later does not count yet.
+        const later_keypair = Keypair.generate();
+        const later = new BN(await pg.connection.getSlot()).add(new BN(1_000_000));
+        await program.methods.setSigner(ed25519Signer(later_keypair.publicKey), true, later, new BN(0), SCOPE_ALL).accounts({
+            config: configPDA,
+            signerRegistry: signerRegistryPDA,
+            payer: pg.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([]).rpc();
+        try {
+            await program.methods.setCosignPolicy(new BN(0), 2).accounts({
+                config: configPDA,
+                signerRegistry: signerRegistryPDA,
+                payer: pg.wallet.publicKey,
+            }).signers([]).rpc();
+            assert.fail("Should have failed");
+        } catch (error) {
+            expect(error.error.errorCode.code).to.eq("InvalidCosignPolicy");
+        }
+        await program.methods.removeSigner(ed25519Signer(later_keypair.publicKey)).accounts({
+            config: configPDA,
+            signerRegistry: signerRegistryPDA,
+            payer: pg.wallet.publicKey,
+        }).signers([]).rpc();
+    });
+
+    it("Rotated config signer under the registry", async () => {
+        // With the registry in use, a config signer outside it is never
+        // trusted, and rotating it out must not give it a grace window.
+        const rogue_keypair = Keypair.generate();
+        for (const signer of [rogue_keypair.publicKey, pg.wallet.publicKey]) {
+            await program.methods.update(ed25519Signer(signer), new BN('2000000'), new BN(1000), new BN(3600)).accounts({
+                config: configPDA,
+                signerHistory: signerHistoryPDA,
+                payer: pg.wallet.publicKey,
+                systemProgram: anchor.web3.SystemProgram.programId,
+            }).signers([]).rpc();
+        }
+        const history = await program.account.signerHistory.fetch(signerHistoryPDA);
+        assert.isFalse(history.signers.some((entry) => entry.key.ed25519[0].equals(rogue_keypair.publicKey)))
+
+        const msgJson = {
+            'domain': domain(KIND_UPLOAD),
+            'expires_at': expiresAt(),
+            'consensus_proof':'9b64d63367328fd980b6e88af0dc46c437bf2c3906a9b000eccd66a6e4599938',
+            'timestamp': uploadDay(11),
+        }
+        const message = Uint8Array.from(
+            Buffer.from(JSON.stringify(msgJson))
+        );
+        const signature = await ed.sign(message, rogue_keypair.secretKey.slice(0, 32));
+        const [userPDA] = PublicKey.findProgramAddressSync(
+            [...consensusSeeds(uploadDay(11)), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        const uploadValidationInstruction = await program.methods.uploadValidation(
+            new BN(uploadDay(11)),
+            Buffer.from(message),
+            Array.from(signature),
+        ).accounts({
+            user:user_keypair.publicKey,
+            config: configPDA,
+            consensus:userPDA,
+            uploadWindow: uploadWindowPDA,
+            signerRegistry: signerRegistryPDA,
+            signerHistory: signerHistoryPDA,
+            ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).instruction()
+        const tx = new anchor.web3.Transaction()
+            .add(anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+                publicKey: rogue_keypair.publicKey.toBytes(),
+                message: message,
+                signature: signature,
+            }))
+            .add(uploadValidationInstruction);
+        try {
+            await anchor.web3.sendAndConfirmTransaction(pg.connection, tx, [user_keypair]);
+            assert.fail("Should have failed");
+        } catch (error) {
+            assert(error.logs.some((log: string) => log.includes("SignerNotAuthorized")))
+        }
+    });
+
+    it("Signature instruction layouts", async () => {
+        // The provider wallet is a registry signer for every scope.
+        const signedUpload = async (day: number) => {
+            const msgJson = {
+                'domain': domain(KIND_UPLOAD),
+                'expires_at': expiresAt(),
+                'consensus_proof':'9b64d63367328fd980b6e88af0dc46c437bf2c3906a9b000eccd66a6e4599938',
+                'timestamp': uploadDay(day),
+            }
+            const message = Buffer.from(JSON.stringify(msgJson));
+            const signature = Buffer.from(await ed.sign(message, pg.wallet.payer.secretKey.slice(0, 32)));
+            const [userPDA] = PublicKey.findProgramAddressSync(
+                [...consensusSeeds(uploadDay(day)), user_keypair.publicKey.toBuffer()],
+                program.programId,
+            );
+            const ix = await program.methods.uploadValidation(
+                new BN(uploadDay(day)),
+                message,
+                Array.from(signature),
+            ).accounts({
+                user:user_keypair.publicKey,
+                config: configPDA,
+                consensus:userPDA,
+                uploadWindow: uploadWindowPDA,
+                signerRegistry: signerRegistryPDA,
+                ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+                systemProgram: anchor.web3.SystemProgram.programId,
+            }).instruction()
+            return {message, signature, userPDA, ix};
+        }
+        const expectUploadFails = async (ixs: anchor.web3.TransactionInstruction[], code: string) => {
+            try {
+                await anchor.web3.sendAndConfirmTransaction(pg.connection, new anchor.web3.Transaction().add(...ixs), [user_keypair]);
+                assert.fail("Should have failed");
+            } catch (error) {
+                assert(error.logs.some((log: string) => log.includes(code)))
+            }
+        }
+        // Ed25519Program data with several entries, each pointing into the
+        // data itself (instruction index 0xffff).
+        const ed25519Ix = (entries: {publicKey: Buffer, signature: Buffer, message: Buffer}[]) => {
+            const header = Buffer.alloc(2 + entries.length * 14);
+            header.writeUInt8(entries.length, 0);
+            const bodies: Buffer[] = [];
+            let at = header.length;
+            entries.forEach((entry, i) => {
+                const o = 2 + i * 14;
+                header.writeUInt16LE(at + 32, o);
+                header.writeUInt16LE(0xffff, o + 2);
+                header.writeUInt16LE(at, o + 4);
+                header.writeUInt16LE(0xffff, o + 6);
+                header.writeUInt16LE(at + 96, o + 8);
+                header.writeUInt16LE(entry.message.length, o + 10);
+                header.writeUInt16LE(0xffff, o + 12);
+                bodies.push(entry.publicKey, entry.signature, entry.message);
+                at += 96 + entry.message.length;
+            });
+            return new anchor.web3.TransactionInstruction({
+                programId: anchor.web3.Ed25519Program.programId,
+                keys: [],
+                data: Buffer.concat([header, ...bodies]),
+            });
+        }
+
+        // One precompile instruction carrying several signatures, the
+        // matching one last.
+        const other_keypair = Keypair.generate();
+        const otherMessage = Buffer.from("unrelated");
+        const first = await signedUpload(12);
+        await sendTx([ed25519Ix([
+            {
+                publicKey: other_keypair.publicKey.toBuffer(),
+                signature: Buffer.from(await ed.sign(otherMessage, other_keypair.secretKey.slice(0, 32))),
+                message: otherMessage,
+            },
+            {publicKey: pg.wallet.publicKey.toBuffer(), signature: first.signature, message: first.message},
+        ]), first.ix], user_keypair);
+        assert((await program.account.consensusState.fetch(first.userPDA)).timestamp.eq(new BN(uploadDay(12))))
+
+        // Signature and message read from our own instruction's data:
+        // discriminator, timestamp and the message length come first.
+        const second = await signedUpload(13);
+        const offsets = Buffer.alloc(16);
+        offsets.writeUInt8(1, 0);
+        offsets.writeUInt16LE(20 + second.message.length, 2);
+        offsets.writeUInt16LE(1, 4);
+        offsets.writeUInt16LE(16, 6);
+        offsets.writeUInt16LE(0xffff, 8);
+        offsets.writeUInt16LE(20, 10);
+        offsets.writeUInt16LE(second.message.length, 12);
+        offsets.writeUInt16LE(1, 14);
+        await sendTx([new anchor.web3.TransactionInstruction({
+            programId: anchor.web3.Ed25519Program.programId,
+            keys: [],
+            data: Buffer.concat([offsets, pg.wallet.publicKey.toBuffer()]),
+        }), second.ix], user_keypair);
+        assert((await program.account.consensusState.fetch(second.userPDA)).timestamp.eq(new BN(uploadDay(13))))
+
+        // Our instruction first, with no precompile in the transaction.
+        const third = await signedUpload(14);
+        await expectUploadFails([third.ix], "SigVerificationFailed");
+
+        // The runtime rejects malformed precompile data before the program
+        // runs, so these are only reachable in a simulation without
+        // signature verification.
+        const valid = ed25519Ix([{publicKey: pg.wallet.publicKey.toBuffer(), signature: third.signature, message: third.message}]);
+        const secp256k1 = anchor.web3.Secp256k1Program.createInstructionWithPrivateKey({
+            privateKey: Keypair.generate().secretKey.slice(0, 32),
+            message: third.message,
+        });
+        const malformed = [
+            // One signature announced, no offsets.
+            new anchor.web3.TransactionInstruction({programId: anchor.web3.Ed25519Program.programId, keys: [], data: Buffer.from([1, 0])}),
+            // Message cut short.
+            new anchor.web3.TransactionInstruction({programId: valid.programId, keys: [], data: valid.data.subarray(0, valid.data.length - 10)}),
+            // Secp256k1 offsets cut short.
+            new anchor.web3.TransactionInstruction({programId: secp256k1.programId, keys: [], data: secp256k1.data.subarray(0, 6)}),
+        ];
+        const {blockhash} = await pg.connection.getLatestBlockhash();
+        for (const ix of malformed) {
+            const tx = new anchor.web3.VersionedTransaction(new anchor.web3.TransactionMessage({
+                payerKey: user_keypair.publicKey,
+                recentBlockhash: blockhash,
+                instructions: [ix, valid, third.ix],
+            }).compileToV0Message());
+            const result = await pg.connection.simulateTransaction(tx, {sigVerify: false});
+            assert.isNotNull(result.value.err)
+            assert(result.value.logs.some((log: string) => log.includes("MalformedSignatureInstruction")))
+        }
+    });
+
+    it("Ethereum signer", async () => {
+        const msgJson = {
+            'domain': domain(KIND_UPLOAD),
+            'expires_at': expiresAt(),
+            'consensus_proof':'9b64d63367328fd980b6e88af0dc46c437bf2c3906a9b000eccd66a6e4599938',
+            'timestamp': uploadDay(10),
+        }
+        const message = Buffer.from(JSON.stringify(msgJson));
+        const sigIx = anchor.web3.Secp256k1Program.createInstructionWithPrivateKey({
+            privateKey: Keypair.generate().secretKey.slice(0, 32),
+            message: message,
+        });
+        // Secp256k1 instruction data: 12-byte header, eth address, signature, recovery id, message.
+        const ethAddress = sigIx.data.subarray(12, 32);
+        const signature = sigIx.data.subarray(32, 96);
+
+        await program.methods.setSigner(ethereumSigner(ethAddress), true, new BN(0), new BN(0), 1).accounts({
+            config: configPDA,
+            signerRegistry: signerRegistryPDA,
+            payer: pg.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([]).rpc();
+
+        const [userPDA] = PublicKey.findProgramAddressSync(
+            [...consensusSeeds(uploadDay(10)), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        const uploadValidationInstruction = await program.methods.uploadValidation(
+            new BN(uploadDay(10)),
+            message,
+            Array.from(signature),
+        ).accounts({
+            user:user_keypair.publicKey,
+            config: configPDA,
+            consensus:userPDA,
+            uploadWindow: uploadWindowPDA,
+            signerRegistry: signerRegistryPDA,
+            ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).instruction()
+        await sendTx([sigIx, uploadValidationInstruction], user_keypair);
+        assert((await program.account.consensusState.fetch(userPDA)).timestamp.eq(new BN(uploadDay(10))))
+
+        await program.methods.removeSigner(ethereumSigner(ethAddress)).accounts({
+            config: configPDA,
+            signerRegistry: signerRegistryPDA,
+            payer: pg.wallet.publicKey,
+        }).signers([]).rpc();
+    });
+
+    it("Pause switches", async () => {
+        const PAUSE_WITHDRAW = 1 << 4;
+        const guardian_keypair = Keypair.generate();
+        await program.methods.setGuardian(guardian_keypair.publicKey).accounts({
+            config: configPDA,
+            payer: pg.wallet.publicKey,
+        }).signers([]).rpc();
+
+        await program.methods.setPause(PAUSE_WITHDRAW).accounts({
+            config: configPDA,
+            authority: guardian_keypair.publicKey,
+        }).signers([guardian_keypair]).rpc();
+        try {
+            await program.methods.withdraw(new BN(1)).accounts({
+                config: configPDA,
+                destination: pg.wallet.publicKey,
+                payer: pg.wallet.publicKey,
+                systemProgram: anchor.web3.SystemProgram.programId,
+            }).signers([]).rpc();
+            assert.fail("Should have failed");
+        } catch (error) {
+            assert(error.logs.some((log: string) => log.includes("Paused")))
+        }
+
+        // The guardian can never unpause.
+        try {
+            await program.methods.setPause(0).accounts({
+                config: configPDA,
+                authority: guardian_keypair.publicKey,
+            }).signers([guardian_keypair]).rpc();
+            assert.fail("Should have failed");
+        } catch (error) {
+            assert(error.logs.some((log: string) => log.includes("GuardianCannotUnpause")))
+        }
+
+        await program.methods.setPause(0).accounts({
+            config: configPDA,
+            authority: pg.wallet.publicKey,
+        }).signers([]).rpc();
+        assert((await program.account.config.fetch(configPDA)).paused == 0)
+    });
+
+    it("Claim Token-2022 reward with transfer fee", async () => {
+        const [statePda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("state")],
+            program.programId);
+        // 1% transfer fee.
+        const mint_keypair = Keypair.generate();
+        const mint = mint_keypair.publicKey;
+        const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+        const mintTx = new anchor.web3.Transaction().add(
+            anchor.web3.SystemProgram.createAccount({
+                fromPubkey: pg.wallet.publicKey,
+                newAccountPubkey: mint,
+                space: mintLen,
+                lamports: await pg.connection.getMinimumBalanceForRentExemption(mintLen),
+                programId: TOKEN_2022_PROGRAM_ID,
+            }),
+            createInitializeTransferFeeConfigInstruction(
+                mint, pg.wallet.publicKey, pg.wallet.publicKey, 100, BigInt(1_000000), TOKEN_2022_PROGRAM_ID),
+            createInitializeMintInstruction(mint, 6, pg.wallet.publicKey, null, TOKEN_2022_PROGRAM_ID),
+        );
+        await pg.sendAndConfirm(mintTx, [mint_keypair]);
+
+        const token_vault_ata = await getAssociatedTokenAddress(mint, statePda, true, TOKEN_2022_PROGRAM_ID);
+        await pg.sendAndConfirm(new anchor.web3.Transaction().add(
+            createAssociatedTokenAccountInstruction(
+                pg.wallet.publicKey, token_vault_ata, statePda, mint, TOKEN_2022_PROGRAM_ID)
+        ), []);
+        await mintTo(pg.connection, pg.wallet.payer, mint, token_vault_ata, pg.wallet.payer,
+            BigInt(1000_000000), [], undefined, TOKEN_2022_PROGRAM_ID);
+
+        // A second reward mint is just another registration.
+        await program.methods.registerRewardMint(true, false).accounts({
+            state: statePda,
+            mint: mint,
+            tokenVault: token_vault_ata,
+            rewardMint: rewardMintPDA(mint),
+            config: configPDA,
+            payer: pg.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([]).rpc();
+        const [limitsPDA] = PublicKey.findProgramAddressSync(
+            [Buffer.from('limits'), mint.toBuffer()],
+            program.programId,
+        );
+        const [usagePDA] = PublicKey.findProgramAddressSync(
+            [Buffer.from('usage'), mint.toBuffer(), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        await program.methods.setMintLimits(new BN(0), new BN(0), new BN(0)).accounts({
+            config: configPDA,
+            mint: mint,
+            limits: limitsPDA,
+            payer: pg.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([]).rpc();
+        await program.methods.setTransferFeeMode(true).accounts({
+            config: configPDA,
+            payer: pg.wallet.publicKey,
+        }).signers([]).rpc();
+
+        const task = 2;
+        const reward = 1000000;
+        const msgJson = {
+            'domain': domain(KIND_CLAIM),
+            'expires_at': expiresAt(),
+            'task':task,
+            'nonce':0,
+            'reward':reward,
+            'receiver': Array.from(user_keypair.publicKey.toBytes()),
+            'mint': Array.from(mint.toBytes()),
+        }
+        const message = Uint8Array.from(
+            Buffer.from(JSON.stringify(msgJson))
+        );
+        const signature = await ed.sign(message, pg.wallet.payer.secretKey.slice(0, 32));
+        const [rewardPDA] = PublicKey.findProgramAddressSync(
+            [Buffer.from('reward'), new BN(task).toArrayLike(Buffer, 'le', 2), mint.toBuffer(), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        const receiver_ata = await getAssociatedTokenAddress(mint, user_keypair.publicKey, false, TOKEN_2022_PROGRAM_ID);
+        const claimIx = await program.methods.claim(
+            task,
+            Buffer.from(message),
+            Array.from(signature),
+        ).accounts({
+            payer:user_keypair.publicKey,
+            config: configPDA,
+            state:statePda,
+            reward:rewardPDA,
+            legacyReward: legacyRewardPDA(task, user_keypair.publicKey),
+            rewardMint: rewardMintPDA(mint),
+            tokenVault:token_vault_ata,
+            receiver:receiver_ata,
+            mint: mint,
+            limits: limitsPDA,
+            usage: usagePDA,
+            profile: profilePDA(user_keypair.publicKey),
+            signerRegistry: signerRegistryPDA,
+            signerHistory: null,
+            ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        }).instruction()
+        const sigIx = anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: pg.publicKey.toBytes(),
+            message: message,
+            signature: signature,
+        });
+
+        // The guardian can stop claims in this mint alone, but only the
+        // owner can resume them.
+        const guardian_keypair = Keypair.generate();
+        await program.methods.setGuardian(guardian_keypair.publicKey).accounts({
+            config: configPDA,
+            payer: pg.wallet.publicKey,
+        }).signers([]).rpc();
+        const setMintPaused = (paused: boolean, authority: Keypair) => program.methods.setMintPaused(paused).accounts({
+            config: configPDA,
+            rewardMint: rewardMintPDA(mint),
+            authority: authority.publicKey,
+        }).signers([authority]).rpc();
+        await setMintPaused(true, guardian_keypair);
+        try {
+            await anchor.web3.sendAndConfirmTransaction(pg.connection, new anchor.web3.Transaction().add(sigIx, claimIx), [user_keypair]);
+            assert.fail("Should have failed");
+        } catch (error) {
+            assert(error.logs.some((log: string) => log.includes("Paused")))
+        }
+        try {
+            await setMintPaused(false, guardian_keypair);
+            assert.fail("Should have failed");
+        } catch (error) {
+            expect(error.error.errorCode.code).to.eq("GuardianCannotUnpause");
+        }
+        await setMintPaused(false, pg.wallet.payer);
+        assert.isFalse((await program.account.rewardMint.fetch(rewardMintPDA(mint))).paused)
+
+        await sendTx([sigIx, claimIx], user_keypair);
+
+        // Grossed up: the receiver gets the full reward, the vault pays the fee on top.
+        const receiver_balance = await pg.connection.getTokenAccountBalance(receiver_ata);
+        expect(receiver_balance.value.amount).to.eq(reward.toString());
+        assert((await program.account.claimReward.fetch(rewardPDA)).received.eq(new BN(reward)))
+
+        await program.methods.setTransferFeeMode(false).accounts({
+            config: configPDA,
+            payer: pg.wallet.publicKey,
+        }).signers([]).rpc();
+    });
+
+    const globalPDA = (epochStart: number) => PublicKey.findProgramAddressSync(
+        [Buffer.from("global"), new BN(epochStart).toArrayLike(Buffer, 'le', 8)],
+        program.programId
+    )[0]
+    const [headPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("global_head")],
+        program.programId
+    );
+    // Anyone may submit and pay; the rollup itself is signed by a key
+    // with SCOPE_GLOBAL in the registry, and by any cosigners.
+    const publishTx = async (epochStart: number, root: Buffer, key: Keypair,
+                             signedEpochStart = epochStart, cosigners: Keypair[] = []) => {
+        const message = Buffer.from(JSON.stringify({
+            'domain': domain(KIND_GLOBAL),
+            'expires_at': expiresAt(),
+            'epoch_start': signedEpochStart,
+            'root': root.toString('hex'),
+            'contributors': 42,
+            'dataset_ids': [1, 2],
+        }))
+        const signature = await ed.sign(message, key.secretKey.slice(0, 32))
+        const ix = await program.methods.publishGlobalConsensus(
+            new BN(epochStart),
+            message,
+            Array.from(signature),
+        ).accounts({
+            config: configPDA,
+            payer: user_keypair.publicKey,
+            uploadWindow: uploadWindowPDA,
+            head: headPDA,
+            global: globalPDA(epochStart),
+            signerRegistry: signerRegistryPDA,
+            signerHistory: null,
+            ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).instruction()
+        const ixs = [anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: key.publicKey.toBytes(),
+            message: message,
+            signature: signature,
+        })]
+        for (const cosigner of cosigners) {
+            ixs.push(anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+                publicKey: cosigner.publicKey.toBytes(),
+                message: message,
+                signature: await ed.sign(message, cosigner.secretKey.slice(0, 32)),
+            }))
+        }
+        ixs.push(ix)
+        return ixs
+    }
+    const publish = async (epochStart: number, root: Buffer, cosigners: Keypair[] = []) =>
+        sendTx(await publishTx(epochStart, root, pg.wallet.payer, epochStart, cosigners), user_keypair)
+    const publishFails = async (epochStart: number, root: Buffer, key: Keypair, code: string,
+                                signedEpochStart = epochStart) => {
+        const tx = new anchor.web3.Transaction().add(...await publishTx(epochStart, root, key, signedEpochStart))
+        try {
+            await anchor.web3.sendAndConfirmTransaction(pg.connection, tx, [user_keypair]);
+            assert.fail("Should have failed");
+        } catch (error) {
+            assert(error.logs.some((log: string) => log.includes(code)))
+        }
+    }
+
+    it("Publish global consensus", async () => {
+        const first = uploadDay(3);
+        const root = Buffer.alloc(32, 1);
+
+        // Only keys trusted for SCOPE_GLOBAL may sign.
+        await publishFails(first, root, user_keypair, "SignerNotAuthorized");
+        // Publications have their own pause flag.
+        const PAUSE_GLOBAL = 1 << 5;
+        for (const paused of [PAUSE_GLOBAL, 0]) {
+            await program.methods.setPause(paused).accounts({
+                config: configPDA,
+                authority: pg.wallet.publicKey,
+            }).signers([]).rpc();
+            if (paused) {
+                await publishFails(first, root, pg.wallet.payer, "Paused");
+            }
+        }
+        // The argument seeds the account and must be the signed epoch.
+        await publishFails(first, root, pg.wallet.payer, "EpochMismatch", first + DAY);
+
+        // Publications need the full cosigner count, even with a threshold
+        // no claim could reach.
+        const cosigner_keypair = Keypair.generate();
+        await program.methods.setSigner(ed25519Signer(cosigner_keypair.publicKey), true, new BN(0), new BN(0), SCOPE_ALL).accounts({
+            config: configPDA,
+            signerRegistry: signerRegistryPDA,
+            payer: pg.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([]).rpc();
+        const setCosignPolicy = (threshold: BN, required: number) => program.methods.setCosignPolicy(threshold, required).accounts({
+            config: configPDA,
+            signerRegistry: signerRegistryPDA,
+            payer: pg.wallet.publicKey,
+        }).signers([]).rpc();
+        await setCosignPolicy(new BN('18446744073709551615'), 2);
+        await publishFails(first, root, pg.wallet.payer, "InsufficientSignatures");
+        await publish(first, root, [cosigner_keypair]);
+        await setCosignPolicy(new BN(0), 1);
+        await program.methods.removeSigner(ed25519Signer(cosigner_keypair.publicKey)).accounts({
+            config: configPDA,
+            signerRegistry: signerRegistryPDA,
+            payer: pg.wallet.publicKey,
+        }).signers([]).rpc();
+
+        await publish(first + DAY, Buffer.alloc(32, 2));
+        const global = await program.account.globalConsensus.fetch(globalPDA(first + DAY));
+        assert(global.prevEpochStart.eq(new BN(first)))
+        assert(global.epochLength.eq(new BN(DAY)))
+        assert(Buffer.from(global.prevRoot).equals(root))
+        assert(global.contributors.eq(new BN(42)))
+
+        // Epochs go forward only, start on an epoch boundary and must have
+        // ended: today's is still open.
+        for (const epochStart of [first - DAY, first + DAY + 1, uploadDay(0), uploadDay(-1)]) {
+            await publishFails(epochStart, root, pg.wallet.payer, "InvalidGlobalEpoch");
+        }
+    });
+
+    it("Verify inclusion", async () => {
+        // Leaves and inner nodes are hashed with distinct prefixes, pairs in
+        // sorted order.
+        const sha256 = (...parts: Buffer[]) => createHash('sha256').update(Buffer.concat(parts)).digest()
+        const leafHash = (leaf: Buffer) => sha256(Buffer.from([0]), leaf)
+        const nodeHash = (a: Buffer, b: Buffer) => Buffer.compare(a, b) <= 0
+            ? sha256(Buffer.from([1]), a, b)
+            : sha256(Buffer.from([1]), b, a)
+        const leaves = [1, 2, 3, 4].map((n) => sha256(Buffer.from([n])))
+        const left = nodeHash(leafHash(leaves[0]), leafHash(leaves[1]))
+        const right = nodeHash(leafHash(leaves[2]), leafHash(leaves[3]))
+        const treeRoot = nodeHash(left, right)
+        const verify = (root: PublicKey, leaf: Buffer, path: Buffer[], hash: object) => program.methods
+            .verifyInclusion(Array.from(leaf), path.map((node) => Array.from(node)), hash)
+            .accounts({root})
+            .view()
+
+        // Against a global root.
+        const epochStart = uploadDay(1);
+        await publish(epochStart, treeRoot);
+        const globalRoot = globalPDA(epochStart);
+        assert.isTrue(await verify(globalRoot, leaves[2], [leafHash(leaves[3]), left], {sha256: {}}))
+        assert.isFalse(await verify(globalRoot, leaves[1], [leafHash(leaves[3]), left], {sha256: {}}))
+        assert.isFalse(await verify(globalRoot, leaves[2], [leafHash(leaves[3]), left], {keccak256: {}}))
+        // An inner node does not pass as a leaf.
+        assert.isFalse(await verify(globalRoot, left, [right], {sha256: {}}))
+        try {
+            await verify(globalRoot, treeRoot, [], {sha256: {}});
+            assert.fail("Should have failed");
+        } catch (error) {
+            assert(error.logs.some((log: string) => log.includes("EmptyProof")))
+        }
+
+        // Against the consensus proof of an upload.
+        const message = Buffer.from(JSON.stringify({
+            'domain': domain(KIND_UPLOAD),
+            'expires_at': expiresAt(),
+            'consensus_proof': treeRoot.toString('hex'),
+            'timestamp': uploadDay(2),
+        }))
+        const signature = await ed.sign(message, pg.wallet.payer.secretKey.slice(0, 32));
+        const [consensusPDA] = PublicKey.findProgramAddressSync(
+            [...consensusSeeds(uploadDay(2)), admin_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        const uploadIx = await program.methods.uploadValidation(
+            new BN(uploadDay(2)),
+            message,
+            Array.from(signature),
+        ).accounts({
+            user: admin_keypair.publicKey,
+            config: configPDA,
+            consensus: consensusPDA,
+            uploadWindow: uploadWindowPDA,
+            signerRegistry: signerRegistryPDA,
+            signerHistory: null,
+            ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).instruction()
+        await sendTx([anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: pg.wallet.publicKey.toBytes(),
+            message: message,
+            signature: signature,
+        }), uploadIx], admin_keypair);
+        assert.isTrue(await verify(consensusPDA, leaves[0], [leafHash(leaves[1]), right], {sha256: {}}))
+        assert.isFalse(await verify(consensusPDA, leaves[0], [leafHash(leaves[2]), right], {sha256: {}}))
+    });
+
+    it("Close consensus", async () => {
+        const [archivePDA] = PublicKey.findProgramAddressSync(
+            [Buffer.from("consensus_archive")],
+            program.programId
+        );
+        // Shrink the window so a retention of a few days is enough.
+        await program.methods.setUploadWindow(new BN(DAY), new BN(2 * DAY), new BN(300)).accounts({
+            config: configPDA,
+            uploadWindow: uploadWindowPDA,
+            payer: pg.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([]).rpc();
+        // Shorter than one epoch plus maxPast, so closed uploads could be replayed.
+        try {
+            await program.methods.setConsensusRetention(new BN(2 * DAY)).accounts({
+                config: configPDA,
+                archive: archivePDA,
+                uploadWindow: uploadWindowPDA,
+                payer: pg.wallet.publicKey,
+                systemProgram: anchor.web3.SystemProgram.programId,
+            }).signers([]).rpc();
+            assert.fail("Should have failed");
+        } catch (error) {
+            expect(error.error.errorCode.code).to.eq("InvalidRetention");
+        }
+        await program.methods.setConsensusRetention(new BN(3 * DAY)).accounts({
+            config: configPDA,
+            archive: archivePDA,
+            uploadWindow: uploadWindowPDA,
+            payer: pg.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([]).rpc();
+
+        // Yesterday's upload is still inside retention.
+        const [recentPDA] = PublicKey.findProgramAddressSync(
+            [...consensusSeeds(uploadDay(1)), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        try {
+            await program.methods.closeConsensus(new BN(uploadDay(1) / DAY)).accounts({
+                user: user_keypair.publicKey,
+                consensus: recentPDA,
+                archive: archivePDA,
+                uploadWindow: uploadWindowPDA,
+            }).signers([user_keypair]).rpc();
+            assert.fail("Should have failed");
+        } catch (error) {
+            expect(error.error.errorCode.code).to.eq("RetentionNotElapsed");
+        }
+
+        // Each closed record is chained as
+        // sha256(root || user || timestamp || consensus_proof || global).
+        let root = Buffer.alloc(32);
+        const chain = (user: PublicKey, state) => {
+            root = createHash("sha256")
+                .update(root)
+                .update(user.toBuffer())
+                .update(new BN(state.timestamp).toArrayLike(Buffer, 'le', 8))
+                .update(Buffer.from(state.consensusProof))
+                .update(Buffer.from([state.global ? 1 : 0]))
+                .digest();
+        }
+
+        // The uploader closes their own account...
+        const [ownPDA] = PublicKey.findProgramAddressSync(
+            [...consensusSeeds(uploadDay(9)), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        chain(user_keypair.publicKey, await program.account.consensusState.fetch(ownPDA));
+        await program.methods.closeConsensus(new BN(uploadDay(9) / DAY)).accounts({
+            user: user_keypair.publicKey,
+            consensus: ownPDA,
+            archive: archivePDA,
+            uploadWindow: uploadWindowPDA,
+        }).signers([user_keypair]).rpc();
+        assert((await pg.connection.getAccountInfo(ownPDA)) == null)
+
+        // ...and the owner closes in batches, refunding the uploader.
+        const [otherPDA] = PublicKey.findProgramAddressSync(
+            [...consensusSeeds(uploadDay(10)), user_keypair.publicKey.toBuffer()],
+            program.programId,
+        );
+        chain(user_keypair.publicKey, await program.account.consensusState.fetch(otherPDA));
+        await program.methods.closeConsensusBatch([new BN(uploadDay(10) / DAY)]).accounts({
+            config: configPDA,
+            archive: archivePDA,
+            uploadWindow: uploadWindowPDA,
+            payer: pg.wallet.publicKey,
+        }).remainingAccounts([
+            {pubkey: otherPDA, isWritable: true, isSigner: false},
+            {pubkey: user_keypair.publicKey, isWritable: true, isSigner: false},
+        ]).signers([]).rpc();
+        assert((await pg.connection.getAccountInfo(otherPDA)) == null)
+        const archive = await program.account.consensusArchive.fetch(archivePDA)
+        assert(archive.count.eq(new BN(2)))
+        assert(Buffer.from(archive.root).equals(root))
+    });
+
+    it("Legacy consensus record", async () => {
+        // Preloaded through Anchor.toml: a pre-versioning ConsensusState at
+        // the old seeds ["1700000000", user], as earlier releases created it.
+        const legacy_user = Keypair.fromSecretKey(Uint8Array.from(
+            JSON.parse(fs.readFileSync("tests/fixtures/legacy_user.json", "utf8"))));
+        const key = 1700000000;
+        const [legacyPDA] = PublicKey.findProgramAddressSync(
+            [Buffer.from(key.toString()), legacy_user.publicKey.toBuffer()],
+            program.programId,
+        );
+        const [archivePDA] = PublicKey.findProgramAddressSync(
+            [Buffer.from("consensus_archive")],
+            program.programId
+        );
+        const closeLegacy = () => program.methods.closeLegacyConsensus(new BN(key)).accounts({
+            user: legacy_user.publicKey,
+            consensus: legacyPDA,
+            archive: archivePDA,
+            uploadWindow: uploadWindowPDA,
+        }).signers([legacy_user]).rpc();
+
+        // The old layout has to be migrated first.
+        try {
+            await closeLegacy();
+            assert.fail("Should have failed");
+        } catch (error) {
+            expect(error.error.errorCode.code).to.eq("AccountDidNotDeserialize");
+        }
+
+        await program.methods.migrateConsensus().accounts({
+            account: legacyPDA,
+            payer: pg.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([]).rpc();
+        const state = await program.account.consensusState.fetch(legacyPDA);
+        assert(state.version == 1);
+        assert(state.timestamp.eq(new BN(key)));
+        assert(Buffer.from(state.consensusProof).equals(createHash("sha256").update("legacy consensus").digest()));
+
+        const before = await program.account.consensusArchive.fetch(archivePDA);
+        await closeLegacy();
+        assert((await pg.connection.getAccountInfo(legacyPDA)) == null)
+        assert((await pg.connection.getBalance(legacy_user.publicKey)) > 0)
+        const root = createHash("sha256")
+            .update(Buffer.from(before.root))
+            .update(legacy_user.publicKey.toBuffer())
+            .update(new BN(key).toArrayLike(Buffer, 'le', 8))
+            .update(Buffer.from(state.consensusProof))
+            .update(Buffer.from([0]))
+            .digest();
+        const archive = await program.account.consensusArchive.fetch(archivePDA);
+        assert(archive.count.eq(before.count.add(new BN(1))))
+        assert(Buffer.from(archive.root).equals(root))
     });
 });
\ No newline at end of file